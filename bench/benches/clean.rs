@@ -0,0 +1,79 @@
+// Copyright (c) 2020-3 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use joat_path::{clean_unix, clean_unix_cow, clean_windows, clean_windows_cow};
+
+const UNIX_PATHS: [(&str, &str); 3] = [
+    (
+        "clean",
+        "/home/user/src/project/target/debug/build/output.rs",
+    ),
+    ("trailing", "/home/user/src/project/target/debug/build/"),
+    (
+        "unclean",
+        "/home/user/./src//project/../project/target/debug/build/output.rs",
+    ),
+];
+
+const WINDOWS_PATHS: [(&str, &str); 3] = [
+    (
+        "clean",
+        "C:\\Users\\user\\src\\project\\target\\debug\\output.rs",
+    ),
+    (
+        "unc",
+        "\\\\server\\share\\src\\project\\target\\debug\\output.rs",
+    ),
+    (
+        "unclean",
+        "C:/Users/user/./src//project/../project/target/debug/output.rs",
+    ),
+];
+
+fn bench_clean_unix(c: &mut Criterion) {
+    let mut group = c.benchmark_group("clean_unix");
+    for (name, path) in UNIX_PATHS {
+        group.bench_with_input(BenchmarkId::new("string", name), path, |b, p| {
+            b.iter(|| clean_unix(black_box(p)));
+        });
+        group.bench_with_input(BenchmarkId::new("cow", name), path, |b, p| {
+            b.iter(|| clean_unix_cow(black_box(p)));
+        });
+    }
+    group.finish();
+}
+
+fn bench_clean_windows(c: &mut Criterion) {
+    let mut group = c.benchmark_group("clean_windows");
+    for (name, path) in WINDOWS_PATHS {
+        group.bench_with_input(BenchmarkId::new("string", name), path, |b, p| {
+            b.iter(|| clean_windows(black_box(p)));
+        });
+        group.bench_with_input(BenchmarkId::new("cow", name), path, |b, p| {
+            b.iter(|| clean_windows_cow(black_box(p)));
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_clean_unix, bench_clean_windows);
+criterion_main!(benches);
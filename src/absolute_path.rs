@@ -19,8 +19,11 @@
 // OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
 // WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 //
-use crate::path_clean::clean;
-use std::io::{Error, ErrorKind, Result};
+use crate::absolute_path_buf::AbsolutePathBuf;
+use crate::error::{Error, Result};
+use crate::flavor::{PathFlavor, PrefixKind, UnixFlavor, WindowsFlavor};
+use crate::path_clean::{clean_os, clean_with};
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
 /// Normalize a target path to an absolute path relative to a base
@@ -31,35 +34,196 @@ use std::path::{Path, PathBuf};
 ///
 /// * `base_dir` - Base directory (must be absolute), typically the current working directory
 /// * `path` - Path
-pub fn absolute_path<B: AsRef<Path>, P: AsRef<Path>>(base_dir: B, path: P) -> Result<PathBuf> {
-    fn normalize(path: &Path) -> Result<PathBuf> {
-        path.to_str()
-            .ok_or_else(|| {
-                Error::new(
-                    ErrorKind::Other,
-                    format!("Path {} cannot be converted to string", path.display()),
-                )
-            })
-            .map(clean)
-            .map(PathBuf::from)
+pub fn absolute_path<B: AsRef<Path>, P: AsRef<Path>>(
+    base_dir: B,
+    path: P,
+) -> Result<AbsolutePathBuf> {
+    if !base_dir.as_ref().is_absolute() {
+        return Err(Error::BaseNotAbsolute(base_dir.as_ref().to_path_buf()));
     }
 
-    if !base_dir.as_ref().is_absolute() {
-        return Err(Error::new(
-            ErrorKind::InvalidInput,
-            format!(
-                "Base directory {} is not absolute",
-                base_dir.as_ref().display()
-            ),
-        ));
+    Ok(absolute_path_unchecked(base_dir.as_ref(), path.as_ref()))
+}
+
+/// Normalize a target path to an absolute path relative to a base
+/// directory that is already known to be absolute
+///
+/// # Arguments
+///
+/// * `base_dir` - Base directory (must be absolute)
+/// * `path` - Path
+pub fn absolute_path_unchecked(base_dir: &Path, path: &Path) -> AbsolutePathBuf {
+    let joined = match path.components().count() {
+        0 => base_dir.to_path_buf(),
+        _ => base_dir.join(path),
+    };
+    AbsolutePathBuf::new_unchecked(PathBuf::from(clean_os(joined.as_os_str())))
+}
+
+/// Normalize a target path to an absolute path relative to a base
+/// directory according to Unix rules
+///
+/// ```rust
+/// use joat_path::absolute_path_unix;
+/// assert_eq!(absolute_path_unix("/aa/bb", "../cc").unwrap(), "/aa/cc");
+/// assert!(absolute_path_unix("aa", "bb").is_err());
+/// ```
+pub fn absolute_path_unix(base_dir: &str, path: &str) -> Result<String> {
+    absolute_path_with::<UnixFlavor>(base_dir, path)
+}
+
+/// Normalize a target path to an absolute path relative to a base
+/// directory according to Windows rules
+///
+/// A path relative to the root of the current drive, e.g. `\aa`, resolves
+/// against the drive or UNC share of the base directory. A path relative
+/// to the current directory of another drive, e.g. `D:aa`, resolves against
+/// the root of that drive. A base directory with a verbatim prefix, e.g.
+/// `\\?\C:\aa`, is not normalized: the path is cleaned on its own and its
+/// segments are appended to the base directory.
+///
+/// ```rust
+/// use joat_path::absolute_path_windows;
+/// assert_eq!(absolute_path_windows("C:\\aa\\bb", "..\\cc").unwrap(), "C:\\aa\\cc");
+/// assert_eq!(absolute_path_windows("C:\\aa\\bb", "\\cc").unwrap(), "C:\\cc");
+/// assert_eq!(absolute_path_windows("C:\\aa\\bb", "c:cc").unwrap(), "C:\\aa\\bb\\cc");
+/// assert_eq!(absolute_path_windows("C:\\aa\\bb", "D:cc").unwrap(), "D:\\cc");
+/// assert_eq!(absolute_path_windows("\\\\?\\C:\\a.\\bb", "..\\cc").unwrap(), "\\\\?\\C:\\a.\\cc");
+/// ```
+pub fn absolute_path_windows(base_dir: &str, path: &str) -> Result<String> {
+    absolute_path_windows_with_drives(base_dir, path, &BTreeMap::new())
+}
+
+/// Normalize a target path to an absolute path relative to a base
+/// directory according to Windows rules and per-drive current directories
+///
+/// A path relative to the current directory of another drive, e.g. `D:aa`,
+/// resolves against the entry for that drive in `drive_dirs` or against the
+/// root of that drive if there is no entry.
+///
+/// # Arguments
+///
+/// * `base_dir` - Base directory (must be absolute), typically the current working directory
+/// * `path` - Path
+/// * `drive_dirs` - Current directories (must be absolute) of drives other than that of `base_dir`, keyed by drive letter
+pub fn absolute_path_windows_with_drives(
+    base_dir: &str,
+    path: &str,
+    drive_dirs: &BTreeMap<char, String>,
+) -> Result<String> {
+    if WindowsFlavor::split_prefix(base_dir).0.is_none() {
+        return Err(Error::BaseNotAbsolute(PathBuf::from(base_dir)));
     }
 
-    normalize(&match path.as_ref().components().count() {
-        0 => base_dir.as_ref().to_path_buf(),
-        _ => base_dir.as_ref().join(path),
+    absolute_path_core::<WindowsFlavor>(base_dir, path, &|drive| {
+        let c = drive.chars().next()?;
+        drive_dirs
+            .get(&c.to_ascii_uppercase())
+            .or_else(|| drive_dirs.get(&c.to_ascii_lowercase()))
+            .map(String::as_str)
     })
 }
 
+/// Normalize a target path to an absolute path relative to a base
+/// directory according to rules of path flavour `P`
+///
+/// # Arguments
+///
+/// * `base_dir` - Base directory (must be absolute)
+/// * `path` - Path
+pub fn absolute_path_with<P: PathFlavor>(base_dir: &str, path: &str) -> Result<String> {
+    absolute_path_core::<P>(base_dir, path, &|_| None)
+}
+
+fn absolute_path_core<'a, P: PathFlavor>(
+    base_dir: &str,
+    path: &str,
+    drive_dir: &dyn Fn(&str) -> Option<&'a str>,
+) -> Result<String> {
+    if !is_absolute::<P>(base_dir) {
+        return Err(Error::BaseNotAbsolute(PathBuf::from(base_dir)));
+    }
+
+    let separator = P::CANONICAL_SEPARATOR;
+    let (base_prefix, _) = P::split_prefix(base_dir);
+    let joined = match P::split_prefix(path) {
+        (None, rest) if base_prefix.is_some_and(|prefix| prefix.kind == PrefixKind::Verbatim) => {
+            let base_prefix = base_prefix.map_or("", |prefix| prefix.text);
+            let base = if P::is_rooted(rest) {
+                base_prefix
+            } else {
+                base_dir
+            };
+            return Ok(join_verbatim::<P>(base, base_prefix.len(), rest));
+        }
+        (Some(prefix), rest) if prefix.kind == PrefixKind::Drive && !P::is_rooted(rest) => {
+            // Path relative to current directory of a drive
+            let dir = match base_prefix {
+                Some(base_prefix) if base_prefix.text.eq_ignore_ascii_case(prefix.text) => {
+                    String::from(base_dir)
+                }
+                _ => match drive_dir(prefix.text) {
+                    Some(dir) if is_verbatim::<P>(dir) => {
+                        let len = P::split_prefix(dir).0.map_or(0, |prefix| prefix.text.len());
+                        return Ok(join_verbatim::<P>(dir, len, rest));
+                    }
+                    Some(dir) if is_absolute::<P>(dir) => String::from(dir),
+                    Some(dir) => return Err(Error::BaseNotAbsolute(PathBuf::from(dir))),
+                    None => String::from(prefix.text),
+                },
+            };
+            format!("{dir}{separator}{rest}")
+        }
+        (Some(_), _) => String::from(path),
+        (None, rest) if P::is_rooted(rest) => {
+            format!("{}{rest}", base_prefix.map_or("", |prefix| prefix.text))
+        }
+        (None, _) => format!("{base_dir}{separator}{path}"),
+    };
+
+    Ok(clean_with::<P>(&joined))
+}
+
+fn is_absolute<P: PathFlavor>(path: &str) -> bool {
+    match P::split_prefix(path) {
+        (Some(prefix), _) if prefix.kind != PrefixKind::Drive => true,
+        (_, rest) => P::is_rooted(rest),
+    }
+}
+
+fn is_verbatim<P: PathFlavor>(path: &str) -> bool {
+    P::split_prefix(path)
+        .0
+        .is_some_and(|prefix| prefix.kind == PrefixKind::Verbatim)
+}
+
+// Verbatim base is used exactly as given, so only the path is cleaned and
+// each `..` removes a segment of the base, never its prefix
+fn join_verbatim<P: PathFlavor>(base_dir: &str, prefix_len: usize, path: &str) -> String {
+    let separator = P::CANONICAL_SEPARATOR;
+    let mut out = String::from(base_dir);
+    for segment in clean_with::<P>(path).split(P::is_separator) {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                while out.len() > prefix_len + 1 && out.ends_with(separator) {
+                    out.pop();
+                }
+                if let Some(i) = out[prefix_len..].rfind(separator) {
+                    out.truncate(prefix_len + i.max(1));
+                }
+            }
+            _ => {
+                if !out.ends_with(separator) {
+                    out.push(separator);
+                }
+                out.push_str(segment);
+            }
+        }
+    }
+    out
+}
+
 #[cfg(test)]
 mod tests {
     use asserts::{check_absolute_path, check_absolute_path_fails};
@@ -110,8 +274,157 @@ mod tests {
         check_absolute_path(abs("/aa/bb/../cc"), rel("dd/../ee"), "/aa/cc/ee", 3);
     }
 
-    mod asserts {
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn path_not_unicode() {
         use crate::absolute_path;
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+        use std::path::Path;
+
+        let p = absolute_path("/aa/bb", OsStr::from_bytes(b"../c\xffc/./dd")).unwrap();
+        assert_eq!(p.as_os_str().as_bytes(), b"/aa/c\xffc/dd");
+        assert_eq!(
+            p.parent().unwrap().as_path(),
+            Path::new("/aa").join(OsStr::from_bytes(b"c\xffc"))
+        );
+    }
+
+    #[test]
+    fn unix() {
+        use crate::absolute_path_unix;
+
+        let tests = vec![
+            ("/aa/bb", "", "/aa/bb"),
+            ("/aa/bb", "cc/../dd", "/aa/bb/dd"),
+            ("/aa/bb/", "../../..", "/"),
+            ("/aa/bb", "/cc/./dd", "/cc/dd"),
+            ("/aa/bb", "C:cc", "/aa/bb/C:cc"),
+            ("/aa/bb", "\\cc", "/aa/bb/\\cc"),
+        ];
+
+        for test in tests {
+            assert_eq!(absolute_path_unix(test.0, test.1).unwrap(), test.2);
+        }
+    }
+
+    #[test]
+    fn windows() {
+        use crate::absolute_path_windows;
+
+        let tests = vec![
+            ("C:\\aa\\bb", "", "C:\\aa\\bb"),
+            ("C:\\aa\\bb", "cc/../dd", "C:\\aa\\bb\\dd"),
+            ("C:/aa/bb", "..\\..\\..", "C:\\"),
+            ("C:\\aa\\bb", "D:\\cc", "D:\\cc"),
+            ("C:\\aa\\bb", "\\cc", "C:\\cc"),
+            ("C:\\aa\\bb", "/cc", "C:\\cc"),
+            ("C:\\aa\\bb", "C:cc", "C:\\aa\\bb\\cc"),
+            ("C:\\aa\\bb", "c:..\\cc", "C:\\aa\\cc"),
+            ("C:\\aa\\bb", "D:", "D:\\"),
+            ("C:\\aa\\bb", "D:cc\\..\\..", "D:\\"),
+            ("\\\\srv\\share\\aa", "bb", "\\\\srv\\share\\aa\\bb"),
+            ("\\\\srv\\share\\aa", "\\bb", "\\\\srv\\share\\bb"),
+            ("\\\\srv\\share\\aa", "..\\..", "\\\\srv\\share\\"),
+            ("\\\\srv\\share\\aa", "D:bb", "D:\\bb"),
+            ("C:\\aa", "\\\\srv\\share\\bb", "\\\\srv\\share\\bb"),
+            ("C:\\aa", "\\\\?\\D:\\bb\\..", "\\\\?\\D:\\bb\\.."),
+        ];
+
+        for test in tests {
+            assert_eq!(absolute_path_windows(test.0, test.1).unwrap(), test.2);
+        }
+    }
+
+    #[test]
+    fn windows_verbatim_base() {
+        use crate::absolute_path_windows;
+
+        let tests = vec![
+            ("\\\\?\\C:\\aa", "bb", "\\\\?\\C:\\aa\\bb"),
+            ("\\\\?\\C:\\aa\\", "bb/./cc", "\\\\?\\C:\\aa\\bb\\cc"),
+            ("\\\\?\\C:\\aa", "", "\\\\?\\C:\\aa"),
+            ("\\\\?\\C:\\aa\\bb", "..\\cc", "\\\\?\\C:\\aa\\cc"),
+            ("\\\\?\\C:\\aa", "..\\..\\cc", "\\\\?\\C:\\cc"),
+            ("\\\\?\\C:\\aa", "..", "\\\\?\\C:\\"),
+            ("\\\\?\\C:\\a/b\\..\\cc", "..", "\\\\?\\C:\\a/b\\.."),
+            ("\\\\?\\C:\\aa\\bb", "\\cc", "\\\\?\\C:\\cc"),
+            ("\\\\?\\C:\\aa", "D:cc", "D:\\cc"),
+            ("\\\\?\\C:\\aa", "E:\\cc", "E:\\cc"),
+            ("\\\\?\\C:\\w", ".\\c:foo", "\\\\?\\C:\\w\\c:foo"),
+            (
+                "\\\\?\\UNC\\srv\\share\\aa",
+                "..\\..\\bb",
+                "\\\\?\\UNC\\srv\\share\\bb",
+            ),
+        ];
+
+        for test in tests {
+            assert_eq!(absolute_path_windows(test.0, test.1).unwrap(), test.2);
+        }
+    }
+
+    #[test]
+    fn windows_with_drives() {
+        use crate::absolute_path_windows_with_drives;
+        use std::collections::BTreeMap;
+
+        let drive_dirs = BTreeMap::from([
+            ('D', String::from("D:\\dd")),
+            ('e', String::from("e:\\ee\\ff")),
+        ]);
+
+        let tests = vec![
+            ("C:\\aa", "D:bb", "D:\\dd\\bb"),
+            ("C:\\aa", "d:bb", "D:\\dd\\bb"),
+            ("C:\\aa", "E:..\\bb", "e:\\ee\\bb"),
+            ("C:\\aa", "F:bb", "F:\\bb"),
+            ("C:\\aa", "C:bb", "C:\\aa\\bb"),
+            ("D:\\aa", "D:bb", "D:\\aa\\bb"),
+            ("C:\\aa", "D:\\bb", "D:\\bb"),
+        ];
+
+        for test in tests {
+            assert_eq!(
+                absolute_path_windows_with_drives(test.0, test.1, &drive_dirs).unwrap(),
+                test.2
+            );
+        }
+
+        let drive_dirs = BTreeMap::from([('D', String::from("\\\\?\\D:\\dd"))]);
+        assert_eq!(
+            absolute_path_windows_with_drives("C:\\aa", "D:..\\bb", &drive_dirs).unwrap(),
+            "\\\\?\\D:\\bb"
+        );
+
+        let drive_dirs = BTreeMap::from([('D', String::from("dd"))]);
+        assert!(matches!(
+            absolute_path_windows_with_drives("C:\\aa", "D:bb", &drive_dirs),
+            Err(crate::Error::BaseNotAbsolute(_))
+        ));
+    }
+
+    #[test]
+    fn flavoured_base_dir_not_absolute() {
+        use crate::{absolute_path_unix, absolute_path_windows, Error};
+
+        for base_dir in ["aa", "", "C:\\aa"] {
+            assert!(matches!(
+                absolute_path_unix(base_dir, "bb"),
+                Err(Error::BaseNotAbsolute(_))
+            ));
+        }
+
+        for base_dir in ["aa", "", "\\aa", "/aa", "C:aa"] {
+            assert!(matches!(
+                absolute_path_windows(base_dir, "bb"),
+                Err(Error::BaseNotAbsolute(_))
+            ));
+        }
+    }
+
+    mod asserts {
+        use crate::{absolute_path, Error};
 
         use super::helpers::{abs, TestPath};
         use super::platform_helpers::{from_test_path, path_component_count, OTHER_SEPARATOR};
@@ -134,7 +447,10 @@ mod tests {
         }
 
         pub fn check_absolute_path_fails(p0: TestPath, p1: TestPath) {
-            assert!(absolute_path(from_test_path(p0), from_test_path(p1)).is_err());
+            assert!(matches!(
+                absolute_path(from_test_path(p0), from_test_path(p1)),
+                Err(Error::BaseNotAbsolute(_))
+            ));
         }
     }
 
@@ -225,7 +541,7 @@ mod tests {
             match iter.next() {
                 Some(RootDir) => {}
                 _ => return None,
-            };
+            }
 
             let mut n = 0;
             loop {
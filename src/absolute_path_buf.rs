@@ -0,0 +1,237 @@
+// Copyright (c) 2020-3 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+use crate::absolute_path::absolute_path_unchecked;
+use crate::error::{Error, Result};
+use std::borrow::Borrow;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+/// Borrowed path that is guaranteed to be absolute and cleaned, analogous
+/// to [`Path`]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(transparent)]
+pub struct AbsolutePath(Path);
+
+/// Owned path that is guaranteed to be absolute and cleaned, analogous to
+/// [`PathBuf`]
+///
+/// ```rust
+/// use joat_path::{absolute_path, AbsolutePathBuf};
+/// use std::path::{Path, PathBuf};
+///
+/// # #[cfg(not(target_os = "windows"))]
+/// # {
+/// let p = absolute_path("/aa/bb", "cc/../dd").unwrap();
+/// assert_eq!(p.as_path(), Path::new("/aa/bb/dd"));
+/// assert_eq!(p.join("../ee").as_path(), Path::new("/aa/bb/ee"));
+///
+/// assert!(AbsolutePathBuf::try_from(PathBuf::from("aa/bb")).is_err());
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct AbsolutePathBuf(PathBuf);
+
+impl AbsolutePath {
+    const fn from_path_unchecked(path: &Path) -> &Self {
+        // SAFETY: AbsolutePath is a transparent wrapper around Path
+        unsafe { &*(std::ptr::from_ref::<Path>(path) as *const Self) }
+    }
+
+    /// Borrow as plain path
+    #[must_use]
+    pub const fn as_path(&self) -> &Path {
+        &self.0
+    }
+
+    /// Create owned copy
+    #[must_use]
+    pub fn to_absolute_path_buf(&self) -> AbsolutePathBuf {
+        AbsolutePathBuf(self.0.to_path_buf())
+    }
+
+    /// Join path onto this path, producing an absolute, cleaned path
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path, which replaces this path if absolute
+    #[must_use]
+    pub fn join<P: AsRef<Path>>(&self, path: P) -> AbsolutePathBuf {
+        absolute_path_unchecked(&self.0, path.as_ref())
+    }
+
+    /// Get parent directory, if any
+    #[must_use]
+    pub fn parent(&self) -> Option<&Self> {
+        self.0.parent().map(Self::from_path_unchecked)
+    }
+}
+
+impl AbsolutePathBuf {
+    pub(crate) const fn new_unchecked(path: PathBuf) -> Self {
+        Self(path)
+    }
+
+    /// Borrow as absolute path
+    #[must_use]
+    pub fn as_absolute_path(&self) -> &AbsolutePath {
+        AbsolutePath::from_path_unchecked(&self.0)
+    }
+
+    /// Convert into plain path
+    #[must_use]
+    pub fn into_path_buf(self) -> PathBuf {
+        self.0
+    }
+}
+
+impl Deref for AbsolutePath {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Deref for AbsolutePathBuf {
+    type Target = AbsolutePath;
+
+    fn deref(&self) -> &AbsolutePath {
+        self.as_absolute_path()
+    }
+}
+
+impl AsRef<Path> for AbsolutePath {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for AbsolutePathBuf {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<AbsolutePath> for AbsolutePathBuf {
+    fn as_ref(&self) -> &AbsolutePath {
+        self.as_absolute_path()
+    }
+}
+
+impl Borrow<AbsolutePath> for AbsolutePathBuf {
+    fn borrow(&self) -> &AbsolutePath {
+        self.as_absolute_path()
+    }
+}
+
+impl ToOwned for AbsolutePath {
+    type Owned = AbsolutePathBuf;
+
+    fn to_owned(&self) -> AbsolutePathBuf {
+        self.to_absolute_path_buf()
+    }
+}
+
+/// Cleans path, failing if path is not absolute
+impl TryFrom<PathBuf> for AbsolutePathBuf {
+    type Error = Error;
+
+    fn try_from(value: PathBuf) -> Result<Self> {
+        if value.is_absolute() {
+            Ok(absolute_path_unchecked(&value, Path::new("")))
+        } else {
+            Err(Error::NotAbsolute(value))
+        }
+    }
+}
+
+impl From<AbsolutePathBuf> for PathBuf {
+    fn from(value: AbsolutePathBuf) -> Self {
+        value.0
+    }
+}
+
+impl PartialEq<Path> for AbsolutePath {
+    fn eq(&self, other: &Path) -> bool {
+        self.0 == *other
+    }
+}
+
+impl PartialEq<Path> for AbsolutePathBuf {
+    fn eq(&self, other: &Path) -> bool {
+        self.0 == *other
+    }
+}
+
+impl PartialEq<PathBuf> for AbsolutePathBuf {
+    fn eq(&self, other: &PathBuf) -> bool {
+        self.0 == *other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AbsolutePathBuf;
+    use crate::error::Error;
+    use crate::test_helpers::abs;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn test_try_from_absolute() {
+        let p = AbsolutePathBuf::try_from(abs("/aa/./bb/../cc/")).unwrap();
+        assert_eq!(p, abs("/aa/cc"));
+    }
+
+    #[test]
+    fn test_try_from_relative_fails() {
+        assert!(matches!(
+            AbsolutePathBuf::try_from(PathBuf::from("aa/bb")),
+            Err(Error::NotAbsolute(_))
+        ));
+    }
+
+    #[test]
+    fn test_join() {
+        let p = AbsolutePathBuf::try_from(abs("/aa/bb")).unwrap();
+        assert_eq!(p.join("cc/../dd"), abs("/aa/bb/dd"));
+        assert_eq!(p.join("../../.."), abs("/"));
+        assert_eq!(p.join(abs("/ee")), abs("/ee"));
+    }
+
+    #[test]
+    fn test_parent() {
+        let p = AbsolutePathBuf::try_from(abs("/aa/bb")).unwrap();
+        let parent = p.parent().unwrap();
+        assert_eq!(*parent, *abs("/aa"));
+        assert_eq!(*parent.parent().unwrap(), *abs("/"));
+        assert!(parent.parent().unwrap().parent().is_none());
+    }
+
+    #[test]
+    fn test_deref() {
+        let p = AbsolutePathBuf::try_from(abs("/aa/bb.txt")).unwrap();
+        assert!(p.is_absolute());
+        assert_eq!(p.extension().unwrap(), "txt");
+        let path: &Path = p.as_ref();
+        assert_eq!(path, abs("/aa/bb.txt"));
+    }
+}
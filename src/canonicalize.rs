@@ -0,0 +1,564 @@
+// Copyright (c) 2020-3 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+use crate::absolute_path::absolute_path_unchecked;
+use crate::absolute_path_buf::AbsolutePathBuf;
+use crate::error::{Error, Result};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::ffi::OsString;
+use std::io::ErrorKind;
+use std::path::{Component, Path, PathBuf};
+
+/// Maximum number of symbolic links followed by [`canonicalize_with`],
+/// matching the limit imposed by Linux
+pub const DEFAULT_MAX_SYMLINKS: usize = 40;
+
+/// Kind of file system entry
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileKind {
+    /// Regular file or other entry that is neither a directory nor a
+    /// symbolic link
+    File,
+
+    /// Directory
+    Dir,
+
+    /// Symbolic link
+    Symlink,
+}
+
+/// File system operations needed to resolve symbolic links
+pub trait Vfs {
+    /// Kind of entry at path, without following symbolic links, analogous
+    /// to [`std::fs::symlink_metadata`]
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Absolute path
+    fn metadata(&self, path: &Path) -> std::io::Result<FileKind>;
+
+    /// Target of symbolic link at path, analogous to [`std::fs::read_link`]
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Absolute path
+    fn read_link(&self, path: &Path) -> std::io::Result<PathBuf>;
+}
+
+/// File system of host operating system
+#[derive(Clone, Copy, Debug, Default)]
+pub struct OsVfs;
+
+impl Vfs for OsVfs {
+    fn metadata(&self, path: &Path) -> std::io::Result<FileKind> {
+        let file_type = std::fs::symlink_metadata(path)?.file_type();
+        Ok(if file_type.is_symlink() {
+            FileKind::Symlink
+        } else if file_type.is_dir() {
+            FileKind::Dir
+        } else {
+            FileKind::File
+        })
+    }
+
+    fn read_link(&self, path: &Path) -> std::io::Result<PathBuf> {
+        std::fs::read_link(path)
+    }
+}
+
+/// In-memory file system, e.g. for testing
+///
+/// Parent directories of entries are created implicitly.
+///
+/// ```rust
+/// use joat_path::{canonicalize_with, MemoryVfs};
+/// use std::path::Path;
+///
+/// # #[cfg(not(target_os = "windows"))]
+/// # {
+/// let vfs = MemoryVfs::new()
+///     .dir("/aa/bb")
+///     .symlink("/cc", "aa/bb");
+/// assert_eq!(canonicalize_with(&vfs, "/cc/../dd").unwrap().as_path(), Path::new("/aa/dd"));
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct MemoryVfs {
+    entries: HashMap<PathBuf, Entry>,
+}
+
+#[derive(Clone, Debug)]
+enum Entry {
+    File,
+    Dir,
+    Symlink(PathBuf),
+}
+
+impl MemoryVfs {
+    /// Empty file system
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add file
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Absolute path
+    #[must_use]
+    pub fn file<P: Into<PathBuf>>(self, path: P) -> Self {
+        self.insert(path.into(), Entry::File)
+    }
+
+    /// Add directory
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Absolute path
+    #[must_use]
+    pub fn dir<P: Into<PathBuf>>(self, path: P) -> Self {
+        self.insert(path.into(), Entry::Dir)
+    }
+
+    /// Add symbolic link
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Absolute path
+    /// * `target` - Target of link, relative to parent of `path` if relative
+    #[must_use]
+    pub fn symlink<P: Into<PathBuf>, T: Into<PathBuf>>(self, path: P, target: T) -> Self {
+        self.insert(path.into(), Entry::Symlink(target.into()))
+    }
+
+    fn insert(mut self, path: PathBuf, entry: Entry) -> Self {
+        for dir in path.ancestors().skip(1) {
+            self.entries.entry(dir.to_path_buf()).or_insert(Entry::Dir);
+        }
+        self.entries.insert(path, entry);
+        self
+    }
+
+    fn get(&self, path: &Path) -> std::io::Result<&Entry> {
+        self.entries
+            .get(path)
+            .ok_or_else(|| std::io::Error::from(ErrorKind::NotFound))
+    }
+}
+
+impl Vfs for MemoryVfs {
+    fn metadata(&self, path: &Path) -> std::io::Result<FileKind> {
+        Ok(match self.get(path)? {
+            Entry::File => FileKind::File,
+            Entry::Dir => FileKind::Dir,
+            Entry::Symlink(_) => FileKind::Symlink,
+        })
+    }
+
+    fn read_link(&self, path: &Path) -> std::io::Result<PathBuf> {
+        match self.get(path)? {
+            Entry::Symlink(target) => Ok(target.clone()),
+            _ => Err(std::io::Error::from(ErrorKind::InvalidInput)),
+        }
+    }
+}
+
+/// Canonicalize absolute path by resolving symbolic links one element at a
+/// time, analogous to [`std::fs::canonicalize`]
+///
+/// Unlike [`std::fs::canonicalize`], the path need not exist: elements that
+/// are not found are kept as they are, as with `realpath -m`.
+/// Fails if a symbolic link loop is detected or if more than
+/// [`DEFAULT_MAX_SYMLINKS`] symbolic links are followed.
+///
+/// # Arguments
+///
+/// * `vfs` - File system
+/// * `path` - Path (must be absolute)
+pub fn canonicalize_with<V: Vfs + ?Sized, P: AsRef<Path>>(
+    vfs: &V,
+    path: P,
+) -> Result<AbsolutePathBuf> {
+    canonicalize_with_limit(vfs, path, DEFAULT_MAX_SYMLINKS)
+}
+
+/// Canonicalize absolute path by resolving symbolic links one element at a
+/// time, following at most `max_symlinks` symbolic links
+///
+/// # Arguments
+///
+/// * `vfs` - File system
+/// * `path` - Path (must be absolute)
+/// * `max_symlinks` - Maximum number of symbolic links to follow
+pub fn canonicalize_with_limit<V: Vfs + ?Sized, P: AsRef<Path>>(
+    vfs: &V,
+    path: P,
+    max_symlinks: usize,
+) -> Result<AbsolutePathBuf> {
+    let path = path.as_ref();
+    if !path.is_absolute() {
+        return Err(Error::NotAbsolute(path.to_path_buf()));
+    }
+
+    Ok(AbsolutePathBuf::new_unchecked(resolve(
+        vfs,
+        path,
+        None,
+        max_symlinks,
+    )?))
+}
+
+/// Resolve path within root directory, analogous to `openat2` with
+/// `RESOLVE_IN_ROOT` on Linux
+///
+/// The path and the targets of any absolute symbolic links are resolved as
+/// if the root directory were the root of the file system, and `..`
+/// elements at the root are discarded, so the result never lies outside
+/// the root directory. As with [`canonicalize_with`], the path need not
+/// exist.
+///
+/// # Arguments
+///
+/// * `root` - Root directory (must be absolute), e.g. root of a container
+///   image; symbolic links within `root` itself are not resolved
+/// * `path` - Path, resolved relative to `root` whether relative or absolute
+pub fn resolve_in_root<R: AsRef<Path>, P: AsRef<Path>>(
+    root: R,
+    path: P,
+) -> Result<AbsolutePathBuf> {
+    resolve_in_root_with(&OsVfs, root, path)
+}
+
+/// Resolve path within root directory using the given file system
+///
+/// ```rust
+/// use joat_path::{resolve_in_root_with, MemoryVfs};
+/// use std::path::Path;
+///
+/// # #[cfg(not(target_os = "windows"))]
+/// # {
+/// let vfs = MemoryVfs::new()
+///     .dir("/image/usr/lib")
+///     .symlink("/image/lib", "/usr/lib");
+/// assert_eq!(
+///     resolve_in_root_with(&vfs, "/image", "/lib/..").unwrap().as_path(),
+///     Path::new("/image/usr")
+/// );
+/// assert_eq!(
+///     resolve_in_root_with(&vfs, "/image", "../../lib/../../../etc").unwrap().as_path(),
+///     Path::new("/image/etc")
+/// );
+/// # }
+/// ```
+///
+/// # Arguments
+///
+/// * `vfs` - File system
+/// * `root` - Root directory (must be absolute)
+/// * `path` - Path, resolved relative to `root` whether relative or absolute
+pub fn resolve_in_root_with<V: Vfs + ?Sized, R: AsRef<Path>, P: AsRef<Path>>(
+    vfs: &V,
+    root: R,
+    path: P,
+) -> Result<AbsolutePathBuf> {
+    let root = root.as_ref();
+    if !root.is_absolute() {
+        return Err(Error::BaseNotAbsolute(root.to_path_buf()));
+    }
+
+    let root = absolute_path_unchecked(root, Path::new(""));
+    Ok(AbsolutePathBuf::new_unchecked(resolve(
+        vfs,
+        path.as_ref(),
+        Some(&root),
+        DEFAULT_MAX_SYMLINKS,
+    )?))
+}
+
+/// Resolve symbolic links in path one element at a time, confining the
+/// result to `jail` if given
+fn resolve<V: Vfs + ?Sized>(
+    vfs: &V,
+    path: &Path,
+    jail: Option<&Path>,
+    max_symlinks: usize,
+) -> Result<PathBuf> {
+    let (mut resolved, names) = match jail {
+        Some(jail) => (jail.to_path_buf(), split_root(path, Some(jail)).1),
+        None => split_root(path, None),
+    };
+    let mut pending = names
+        .into_iter()
+        .map(Pending::Name)
+        .collect::<VecDeque<_>>();
+    // Kind of last element resolved or None if it was not found
+    let mut last_kind = Some(FileKind::Dir);
+    let mut symlinks = 0;
+    // Symbolic links whose targets are currently being resolved
+    let mut active = HashSet::new();
+
+    while let Some(item) = pending.pop_front() {
+        let name = match item {
+            Pending::Name(name) => name,
+            Pending::EndLink(link) => {
+                active.remove(&link);
+                continue;
+            }
+        };
+
+        if last_kind == Some(FileKind::File) {
+            return Err(Error::Io(std::io::Error::from(ErrorKind::NotADirectory)));
+        }
+
+        if name == ".." {
+            if jail.is_none_or(|jail| resolved != jail) {
+                resolved.pop();
+            }
+            last_kind = Some(FileKind::Dir);
+            continue;
+        }
+
+        resolved.push(&name);
+        last_kind = match vfs.metadata(&resolved) {
+            Ok(kind) => Some(kind),
+            Err(e) if e.kind() == ErrorKind::NotFound => None,
+            Err(e) => return Err(Error::Io(e)),
+        };
+
+        if last_kind == Some(FileKind::Symlink) {
+            symlinks += 1;
+            if symlinks > max_symlinks {
+                return Err(Error::TooManySymlinks(path.to_path_buf()));
+            }
+            if !active.insert(resolved.clone()) {
+                return Err(Error::SymlinkLoop(path.to_path_buf()));
+            }
+
+            let target = vfs.read_link(&resolved).map_err(Error::Io)?;
+            pending.push_front(Pending::EndLink(resolved.clone()));
+            resolved.pop();
+            let (root, names) = split_root(&target, Some(&resolved));
+            resolved = match jail {
+                // Target has root or prefix, so resolve from jail instead
+                Some(jail) if root != resolved => jail.to_path_buf(),
+                _ => root,
+            };
+            for name in names.into_iter().rev() {
+                pending.push_front(Pending::Name(name));
+            }
+            last_kind = Some(FileKind::Dir);
+        }
+    }
+
+    Ok(resolved)
+}
+
+enum Pending {
+    Name(OsString),
+    EndLink(PathBuf),
+}
+
+/// Split path into root, which is `base` for relative paths, and queue of
+/// remaining elements other than `.`
+fn split_root(path: &Path, base: Option<&Path>) -> (PathBuf, VecDeque<OsString>) {
+    let mut root = PathBuf::new();
+    let mut pending = VecDeque::new();
+    for component in path.components() {
+        match component {
+            Component::Prefix(prefix) => root.push(prefix.as_os_str()),
+            Component::RootDir => {
+                if root.as_os_str().is_empty() {
+                    // Rooted path without prefix takes prefix from base
+                    if let Some(Component::Prefix(prefix)) =
+                        base.and_then(|base| base.components().next())
+                    {
+                        root.push(prefix.as_os_str());
+                    }
+                }
+                root.push(component.as_os_str());
+            }
+            Component::CurDir => {}
+            Component::ParentDir | Component::Normal(_) => {
+                pending.push_back(component.as_os_str().to_os_string());
+            }
+        }
+    }
+
+    match base {
+        Some(base) if root.as_os_str().is_empty() => (base.to_path_buf(), pending),
+        _ => (root, pending),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{canonicalize_with, canonicalize_with_limit, resolve_in_root_with, MemoryVfs};
+    use crate::error::Error;
+    use crate::test_helpers::abs;
+
+    fn vfs() -> MemoryVfs {
+        MemoryVfs::new()
+            .file(abs("/aa/bb/file"))
+            .dir(abs("/aa/cc"))
+            .symlink(abs("/aa/rel"), "bb")
+            .symlink(abs("/aa/up"), "../aa/cc")
+            .symlink(abs("/abs"), abs("/aa/bb"))
+            .symlink(abs("/chain"), abs("/abs"))
+            .symlink(abs("/aa/file_link"), "bb/file")
+            .symlink(abs("/dangling"), abs("/missing/xx"))
+            .symlink(abs("/loop1"), abs("/loop2"))
+            .symlink(abs("/loop2"), abs("/loop1/xx"))
+            .symlink(abs("/self"), ".")
+            .symlink(abs("/me"), "me")
+    }
+
+    #[test]
+    fn test_canonicalize() {
+        let vfs = vfs();
+        let tests = vec![
+            ("/", "/"),
+            ("/aa/./bb/file", "/aa/bb/file"),
+            ("/aa/rel/file", "/aa/bb/file"),
+            ("/aa/rel/..", "/aa"),
+            ("/aa/up", "/aa/cc"),
+            ("/abs/../cc", "/aa/cc"),
+            ("/chain/file", "/aa/bb/file"),
+            ("/aa/file_link", "/aa/bb/file"),
+            ("/self/self/aa", "/aa"),
+            ("/../aa/rel", "/aa/bb"),
+        ];
+
+        for test in tests {
+            assert_eq!(canonicalize_with(&vfs, abs(test.0)).unwrap(), abs(test.1));
+        }
+    }
+
+    #[test]
+    fn test_canonicalize_missing() {
+        let vfs = vfs();
+        let tests = vec![
+            ("/aa/rel/missing", "/aa/bb/missing"),
+            ("/aa/rel/missing/../xx/./yy", "/aa/bb/xx/yy"),
+            ("/dangling/yy", "/missing/xx/yy"),
+            ("/missing/../abs", "/aa/bb"),
+        ];
+
+        for test in tests {
+            assert_eq!(canonicalize_with(&vfs, abs(test.0)).unwrap(), abs(test.1));
+        }
+    }
+
+    #[test]
+    fn test_canonicalize_fails() {
+        let vfs = vfs();
+        assert!(matches!(
+            canonicalize_with(&vfs, "aa"),
+            Err(Error::NotAbsolute(_))
+        ));
+        assert!(matches!(
+            canonicalize_with(&vfs, abs("/loop1")),
+            Err(Error::SymlinkLoop(_))
+        ));
+        assert!(matches!(
+            canonicalize_with(&vfs, abs("/me")),
+            Err(Error::SymlinkLoop(_))
+        ));
+        assert!(matches!(
+            canonicalize_with(&vfs, abs("/aa/bb/file/xx")),
+            Err(Error::Io(e)) if e.kind() == std::io::ErrorKind::NotADirectory
+        ));
+    }
+
+    #[test]
+    fn test_canonicalize_limit() {
+        let vfs = vfs();
+        assert!(canonicalize_with_limit(&vfs, abs("/chain"), 2).is_ok());
+        assert!(matches!(
+            canonicalize_with_limit(&vfs, abs("/chain"), 1),
+            Err(Error::TooManySymlinks(_))
+        ));
+    }
+
+    fn image_vfs() -> MemoryVfs {
+        MemoryVfs::new()
+            .file(abs("/image/etc/passwd"))
+            .dir(abs("/image/usr/lib"))
+            .symlink(abs("/image/lib"), abs("/usr/lib"))
+            .symlink(abs("/image/etc/escape"), "../../../../etc/passwd")
+            .symlink(abs("/image/etc/host"), abs("/etc/passwd"))
+            .symlink(abs("/image/root"), abs("/"))
+            .symlink(abs("/image/loop"), abs("/loop"))
+    }
+
+    #[test]
+    fn test_resolve_in_root() {
+        let vfs = image_vfs();
+        let tests = vec![
+            ("", "/image"),
+            ("/", "/image"),
+            ("..", "/image"),
+            ("/lib", "/image/usr/lib"),
+            ("lib/../bin", "/image/usr/bin"),
+            ("/etc/escape", "/image/etc/passwd"),
+            ("/etc/host", "/image/etc/passwd"),
+            ("/root/root/lib", "/image/usr/lib"),
+            ("/../../missing/../lib", "/image/usr/lib"),
+            ("/etc/missing/../../..", "/image"),
+        ];
+
+        for test in tests {
+            assert_eq!(
+                resolve_in_root_with(&vfs, abs("/image/"), test.0).unwrap(),
+                abs(test.1)
+            );
+        }
+    }
+
+    #[test]
+    fn test_resolve_in_root_fails() {
+        let vfs = image_vfs();
+        assert!(matches!(
+            resolve_in_root_with(&vfs, "image", "lib"),
+            Err(Error::BaseNotAbsolute(_))
+        ));
+        assert!(matches!(
+            resolve_in_root_with(&vfs, abs("/image"), "loop"),
+            Err(Error::SymlinkLoop(_))
+        ));
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn test_os_vfs() {
+        use super::OsVfs;
+        use std::os::unix::fs::symlink;
+
+        let dir = std::env::temp_dir().join(format!("joat-path-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("aa")).unwrap();
+        symlink("aa", dir.join("link")).unwrap();
+
+        let dir = std::fs::canonicalize(&dir).unwrap();
+        let result = canonicalize_with(&OsVfs, dir.join("link/missing"));
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(result.unwrap(), dir.join("aa/missing"));
+    }
+}
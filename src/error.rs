@@ -0,0 +1,186 @@
+// Copyright (c) 2020-3 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+/// Error returned by functions in this crate
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// Base directory is not absolute
+    BaseNotAbsolute(PathBuf),
+
+    /// Path is not absolute
+    NotAbsolute(PathBuf),
+
+    /// Path is not relative
+    NotRelative(PathBuf),
+
+    /// Path cannot be converted to string
+    NotUnicode(PathBuf),
+
+    /// Path would escape root directory: `root` is `.` for relative paths
+    /// that must not begin with `..`
+    EscapesRoot { root: PathBuf, path: PathBuf },
+
+    /// Paths have different prefixes (e.g. drive letters or UNC shares) or
+    /// only one of the paths is rooted
+    PrefixMismatch { from: PathBuf, to: PathBuf },
+
+    /// Relative path between two paths cannot be determined lexically
+    Indeterminate { from: PathBuf, to: PathBuf },
+
+    /// Path cannot be translated between Unix and Windows, e.g. because
+    /// its drive is not mapped
+    NotTranslatable(PathBuf),
+
+    /// URL is not a valid `file:` URL
+    InvalidUrl(String),
+
+    /// Home directory named by `~` or `~user` element is not known
+    HomeNotFound(String),
+
+    /// Variable referred to by path is not defined
+    VarNotFound(String),
+
+    /// Symbolic link loop encountered while resolving path
+    SymlinkLoop(PathBuf),
+
+    /// Too many symbolic links encountered while resolving path
+    TooManySymlinks(PathBuf),
+
+    /// Path is absolute but not on a drive, e.g. a UNC share or a verbatim
+    /// path, and so cannot be used as a per-drive Windows current directory
+    UncCurrentDir(PathBuf),
+
+    /// Separator requested in cleaning options is not a separator of the
+    /// path flavour
+    InvalidSeparator(char),
+
+    /// I/O error
+    Io(std::io::Error),
+}
+
+/// Result type returned by functions in this crate
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::BaseNotAbsolute(path) => {
+                write!(f, "Base directory {} is not absolute", path.display())
+            }
+            Self::NotAbsolute(path) => write!(f, "Path {} is not absolute", path.display()),
+            Self::NotRelative(path) => write!(f, "Path {} is not relative", path.display()),
+            Self::NotUnicode(path) => {
+                write!(f, "Path {} cannot be converted to string", path.display())
+            }
+            Self::EscapesRoot { root, path } => write!(
+                f,
+                "Path {} escapes root directory {}",
+                path.display(),
+                root.display()
+            ),
+            Self::PrefixMismatch { from, to } => write!(
+                f,
+                "Paths {} and {} do not share a common root",
+                from.display(),
+                to.display()
+            ),
+            Self::Indeterminate { from, to } => write!(
+                f,
+                "Path from {} to {} cannot be determined lexically",
+                from.display(),
+                to.display()
+            ),
+            Self::NotTranslatable(path) => {
+                write!(f, "Path {} cannot be translated", path.display())
+            }
+            Self::InvalidUrl(url) => write!(f, "URL {url} is not a valid file URL"),
+            Self::HomeNotFound(user) => write!(f, "Home directory for {user} not found"),
+            Self::VarNotFound(name) => write!(f, "Variable {name} is not defined"),
+            Self::SymlinkLoop(path) => {
+                write!(f, "Symbolic link loop in path {}", path.display())
+            }
+            Self::TooManySymlinks(path) => {
+                write!(f, "Too many symbolic links in path {}", path.display())
+            }
+            Self::UncCurrentDir(path) => write!(
+                f,
+                "Path {} is not on a drive and cannot be a current directory",
+                path.display()
+            ),
+            Self::InvalidSeparator(c) => write!(f, "Separator {c:?} is not valid"),
+            Self::Io(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<Error> for std::io::Error {
+    fn from(value: Error) -> Self {
+        let kind = match value {
+            Error::Io(e) => return e,
+            Error::NotUnicode(_) => ErrorKind::InvalidData,
+            _ => ErrorKind::InvalidInput,
+        };
+        Self::new(kind, value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Error;
+    use std::io::ErrorKind;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_display() {
+        let e = Error::EscapesRoot {
+            root: PathBuf::from("/srv"),
+            path: PathBuf::from("../x"),
+        };
+        assert_eq!(e.to_string(), "Path ../x escapes root directory /srv");
+    }
+
+    #[test]
+    fn test_into_io_error() {
+        let e = std::io::Error::from(Error::BaseNotAbsolute(PathBuf::from("aa")));
+        assert_eq!(e.kind(), ErrorKind::InvalidInput);
+        assert!(matches!(
+            e.into_inner().unwrap().downcast::<Error>().map(|e| *e),
+            Ok(Error::BaseNotAbsolute(_))
+        ));
+
+        let e = std::io::Error::from(Error::NotUnicode(PathBuf::from("aa")));
+        assert_eq!(e.kind(), ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,425 @@
+// Copyright (c) 2020-3 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+use crate::case_fold::fold_case;
+use crate::error::{Error, Result};
+use std::collections::HashMap;
+
+/// Source of variables used to expand variable references in paths
+pub trait VarSource {
+    /// Value of named variable, if defined
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Variable name
+    fn var(&self, name: &str) -> Option<String>;
+
+    /// Value of named variable, if defined, matching name
+    /// case-insensitively as Windows does
+    ///
+    /// Default implementation matches name exactly.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Variable name
+    fn var_ignore_case(&self, name: &str) -> Option<String> {
+        self.var(name)
+    }
+}
+
+/// Variables taken from the environment of the current process
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemVarSource;
+
+impl VarSource for SystemVarSource {
+    fn var(&self, name: &str) -> Option<String> {
+        std::env::var(name).ok()
+    }
+
+    fn var_ignore_case(&self, name: &str) -> Option<String> {
+        self.var(name).or_else(|| {
+            let vars = std::env::vars_os()
+                .filter_map(|(k, v)| Some((k.into_string().ok()?, v.into_string().ok()?)));
+            find_ignore_case(vars, name)
+        })
+    }
+}
+
+/// Fixed set of variables, e.g. for testing
+#[derive(Clone, Debug, Default)]
+pub struct MapVarSource {
+    vars: HashMap<String, String>,
+}
+
+impl MapVarSource {
+    /// Source with no variables
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Define variable
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Variable name
+    /// * `value` - Value
+    #[must_use]
+    pub fn var(mut self, name: &str, value: &str) -> Self {
+        self.vars.insert(String::from(name), String::from(value));
+        self
+    }
+}
+
+impl VarSource for MapVarSource {
+    fn var(&self, name: &str) -> Option<String> {
+        self.vars.get(name).cloned()
+    }
+
+    fn var_ignore_case(&self, name: &str) -> Option<String> {
+        self.var(name).or_else(|| {
+            let vars = self.vars.iter().map(|(k, v)| (k.as_str(), v.as_str()));
+            find_ignore_case(vars, name).map(String::from)
+        })
+    }
+}
+
+// Picks smallest matching name so that result does not depend on
+// iteration order when several names differ only by case
+fn find_ignore_case<K: AsRef<str> + Ord, V>(
+    vars: impl Iterator<Item = (K, V)>,
+    name: &str,
+) -> Option<V> {
+    vars.filter(|(k, _)| {
+        k.as_ref()
+            .chars()
+            .map(fold_case)
+            .eq(name.chars().map(fold_case))
+    })
+    .min_by(|(a, _), (b, _)| a.cmp(b))
+    .map(|(_, v)| v)
+}
+
+/// Behaviour when a path refers to an undefined variable
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExpandMode {
+    /// Fail with [`crate::Error::VarNotFound`]
+    Strict,
+
+    /// Leave variable reference unexpanded
+    Lenient,
+}
+
+#[derive(Clone, Copy)]
+enum Syntax {
+    Unix,
+    Windows,
+}
+
+enum Reference<'a> {
+    Escape,
+    Var { name: &'a str, len: usize },
+    Literal,
+}
+
+/// Expand variable references in path using syntax of host operating
+/// system
+///
+/// # Arguments
+///
+/// * `path` - Path
+/// * `vars` - Source of variables
+/// * `mode` - Behaviour for undefined variables
+pub fn expand_vars<V: VarSource + ?Sized>(
+    path: &str,
+    vars: &V,
+    mode: ExpandMode,
+) -> Result<String> {
+    if cfg!(target_os = "windows") {
+        expand_vars_windows(path, vars, mode)
+    } else {
+        expand_vars_unix(path, vars, mode)
+    }
+}
+
+/// Expand `$VAR` and `${VAR}` references in path, with `$$` producing a
+/// literal `$`
+///
+/// A `$` that does not begin a reference is left unchanged.
+///
+/// ```rust
+/// use joat_path::{clean_unix, expand_vars_unix, ExpandMode, MapVarSource};
+///
+/// let vars = MapVarSource::new().var("REPO", "/src/repo");
+/// let path = expand_vars_unix("${REPO}/../out/$$x", &vars, ExpandMode::Strict).unwrap();
+/// assert_eq!(clean_unix(&path), "/src/out/$x");
+/// assert!(expand_vars_unix("$HOME/out", &vars, ExpandMode::Strict).is_err());
+/// assert_eq!(expand_vars_unix("$HOME/out", &vars, ExpandMode::Lenient).unwrap(), "$HOME/out");
+/// ```
+pub fn expand_vars_unix<V: VarSource + ?Sized>(
+    path: &str,
+    vars: &V,
+    mode: ExpandMode,
+) -> Result<String> {
+    expand(path, vars, mode, Syntax::Unix)
+}
+
+/// Expand `%VAR%` references in path, with `%%` producing a literal `%`
+///
+/// A `%` that does not begin a reference is left unchanged. Variable names
+/// are matched case-insensitively, as on Windows.
+///
+/// ```rust
+/// use joat_path::{clean_windows, expand_vars_windows, ExpandMode, MapVarSource};
+///
+/// let vars = MapVarSource::new().var("LOCALAPPDATA", "C:\\Users\\me\\AppData\\Local");
+/// let path = expand_vars_windows("%LocalAppData%\\tool", &vars, ExpandMode::Strict).unwrap();
+/// assert_eq!(clean_windows(&path), "C:\\Users\\me\\AppData\\Local\\tool");
+/// ```
+pub fn expand_vars_windows<V: VarSource + ?Sized>(
+    path: &str,
+    vars: &V,
+    mode: ExpandMode,
+) -> Result<String> {
+    expand(path, vars, mode, Syntax::Windows)
+}
+
+fn expand<V: VarSource + ?Sized>(
+    path: &str,
+    vars: &V,
+    mode: ExpandMode,
+    syntax: Syntax,
+) -> Result<String> {
+    let sigil = match syntax {
+        Syntax::Unix => '$',
+        Syntax::Windows => '%',
+    };
+
+    let mut out = String::with_capacity(path.len());
+    let mut rest = path;
+    while let Some(i) = rest.find(sigil) {
+        out.push_str(&rest[..i]);
+        rest = &rest[i..];
+
+        let reference = match syntax {
+            Syntax::Unix => parse_unix(&rest[1..]),
+            Syntax::Windows => parse_windows(&rest[1..]),
+        };
+        match reference {
+            Reference::Escape => {
+                out.push(sigil);
+                rest = &rest[2..];
+            }
+            Reference::Var { name, len } => {
+                let value = match syntax {
+                    Syntax::Unix => vars.var(name),
+                    Syntax::Windows => vars.var_ignore_case(name),
+                };
+                match (value, mode) {
+                    (Some(value), _) => out.push_str(&value),
+                    (None, ExpandMode::Strict) => {
+                        return Err(Error::VarNotFound(String::from(name)))
+                    }
+                    (None, ExpandMode::Lenient) => out.push_str(&rest[..len]),
+                }
+                rest = &rest[len..];
+            }
+            Reference::Literal => {
+                out.push(sigil);
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+fn parse_unix(s: &str) -> Reference<'_> {
+    fn name_len(s: &str) -> usize {
+        match s.as_bytes().first() {
+            Some(b) if b.is_ascii_alphabetic() || *b == b'_' => s
+                .bytes()
+                .take_while(|b| b.is_ascii_alphanumeric() || *b == b'_')
+                .count(),
+            _ => 0,
+        }
+    }
+
+    if s.starts_with('$') {
+        return Reference::Escape;
+    }
+
+    if let Some(braced) = s.strip_prefix('{') {
+        let n = name_len(braced);
+        return if n > 0 && braced[n..].starts_with('}') {
+            Reference::Var {
+                name: &braced[..n],
+                len: n + 3,
+            }
+        } else {
+            Reference::Literal
+        };
+    }
+
+    match name_len(s) {
+        0 => Reference::Literal,
+        n => Reference::Var {
+            name: &s[..n],
+            len: n + 1,
+        },
+    }
+}
+
+fn parse_windows(s: &str) -> Reference<'_> {
+    if s.starts_with('%') {
+        return Reference::Escape;
+    }
+
+    match s.find('%') {
+        Some(n) => Reference::Var {
+            name: &s[..n],
+            len: n + 2,
+        },
+        None => Reference::Literal,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{expand_vars_unix, expand_vars_windows, ExpandMode, MapVarSource};
+    use crate::error::Error;
+
+    fn vars() -> MapVarSource {
+        MapVarSource::new()
+            .var("REPO", "/src/repo")
+            .var("A_1", "aa")
+            .var("EMPTY", "")
+    }
+
+    #[test]
+    fn test_unix() {
+        let vars = vars();
+        let tests = vec![
+            ("$REPO/out", "/src/repo/out"),
+            ("${REPO}/out", "/src/repo/out"),
+            ("x${A_1}x", "xaax"),
+            ("$A_1.txt", "aa.txt"),
+            ("$A_1x", "$A_1x"),
+            ("$EMPTY/out", "/out"),
+            ("$$REPO", "$REPO"),
+            ("$$$A_1", "$aa"),
+            ("a$", "a$"),
+            ("$1/$-/$ /${", "$1/$-/$ /${"),
+            ("${REPO", "${REPO"),
+            ("${RE PO}", "${RE PO}"),
+            ("${}", "${}"),
+            ("%A_1%", "%A_1%"),
+            ("caf\u{e9}/$A_1", "caf\u{e9}/aa"),
+        ];
+
+        for test in tests {
+            assert_eq!(
+                expand_vars_unix(test.0, &vars, ExpandMode::Lenient).unwrap(),
+                test.1
+            );
+        }
+    }
+
+    #[test]
+    fn test_unix_strict() {
+        let vars = vars();
+        assert_eq!(
+            expand_vars_unix("${REPO}/$A_1", &vars, ExpandMode::Strict).unwrap(),
+            "/src/repo/aa"
+        );
+        assert!(matches!(
+            expand_vars_unix("$REPO/${MISSING}", &vars, ExpandMode::Strict),
+            Err(Error::VarNotFound(name)) if name == "MISSING"
+        ));
+        assert_eq!(
+            expand_vars_unix("$$MISSING/$", &vars, ExpandMode::Strict).unwrap(),
+            "$MISSING/$"
+        );
+    }
+
+    #[test]
+    fn test_windows() {
+        let vars = vars();
+        let tests = vec![
+            ("%REPO%\\out", "/src/repo\\out"),
+            ("x%A_1%x%A_1%", "xaaxaa"),
+            ("%EMPTY%out", "out"),
+            ("%%A_1%%", "%A_1%"),
+            ("%%%A_1%", "%aa"),
+            ("100%", "100%"),
+            ("%MISSING%\\%A_1%", "%MISSING%\\aa"),
+            ("$A_1", "$A_1"),
+            ("%repo%\\%a_1%", "/src/repo\\aa"),
+        ];
+
+        for test in tests {
+            assert_eq!(
+                expand_vars_windows(test.0, &vars, ExpandMode::Lenient).unwrap(),
+                test.1
+            );
+        }
+    }
+
+    #[test]
+    fn test_windows_strict() {
+        let vars = vars();
+        assert!(matches!(
+            expand_vars_windows("%MISSING%\\out", &vars, ExpandMode::Strict),
+            Err(Error::VarNotFound(name)) if name == "MISSING"
+        ));
+        assert_eq!(
+            expand_vars_windows("50%%", &vars, ExpandMode::Strict).unwrap(),
+            "50%"
+        );
+    }
+
+    #[test]
+    fn test_windows_case_insensitive() {
+        let vars = MapVarSource::new()
+            .var("LOCALAPPDATA", "C:\\Local")
+            .var("Path", "exact")
+            .var("PATH", "upper")
+            .var("\u{c4}X", "umlaut");
+        let tests = vec![
+            ("%LocalAppData%\\tool", "C:\\Local\\tool"),
+            ("%localappdata%", "C:\\Local"),
+            ("%Path%", "exact"),
+            ("%path%", "upper"),
+            ("%\u{e4}x%", "umlaut"),
+        ];
+
+        for test in tests {
+            assert_eq!(
+                expand_vars_windows(test.0, &vars, ExpandMode::Strict).unwrap(),
+                test.1
+            );
+        }
+        assert_eq!(
+            expand_vars_unix("$LocalAppData", &vars, ExpandMode::Lenient).unwrap(),
+            "$LocalAppData"
+        );
+    }
+}
@@ -0,0 +1,423 @@
+// Copyright (c) 2020-3 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+use crate::error::{Error, Result};
+use crate::path_clean::{clean_unix, clean_windows};
+use crate::translate::is_literal_on_unix;
+use crate::windows_prefix::WindowsPrefix;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+/// Convert absolute path to `file:` URL according to rules of host
+/// operating system
+///
+/// # Arguments
+///
+/// * `path` - Path (must be absolute)
+pub fn path_to_file_url<P: AsRef<Path>>(path: P) -> Result<String> {
+    let path = path.as_ref();
+    let s = path
+        .to_str()
+        .ok_or_else(|| Error::NotUnicode(path.to_path_buf()))?;
+    if cfg!(target_os = "windows") {
+        windows_to_file_url(s)
+    } else {
+        unix_to_file_url(s)
+    }
+}
+
+/// Convert absolute Unix path to cleaned `file:` URL
+///
+/// ```rust
+/// use joat_path::unix_to_file_url;
+/// assert_eq!(unix_to_file_url("/home/me/a b/../c#").unwrap(), "file:///home/me/c%23");
+/// ```
+pub fn unix_to_file_url(path: &str) -> Result<String> {
+    let path_clean = clean_unix(path);
+    if !path_clean.starts_with('/') {
+        return Err(Error::NotAbsolute(PathBuf::from(path)));
+    }
+
+    let mut url = String::from("file://");
+    encode(&path_clean, &mut url);
+    Ok(url)
+}
+
+/// Convert absolute Windows path to cleaned `file:` URL
+///
+/// Paths on drives produce URLs such as `file:///C:/x` and UNC paths
+/// produce URLs such as `file://server/share/x`. Fails for verbatim paths
+/// containing `.` or `..` names or `/`, which URLs cannot express literally.
+///
+/// ```rust
+/// use joat_path::windows_to_file_url;
+/// assert_eq!(windows_to_file_url("C:\\a b\\.\\c").unwrap(), "file:///C:/a%20b/c");
+/// assert_eq!(windows_to_file_url("\\\\srv\\share\\x").unwrap(), "file://srv/share/x");
+/// ```
+pub fn windows_to_file_url(path: &str) -> Result<String> {
+    let path_clean = clean_windows(path);
+    let mut url = String::from("file://");
+    match WindowsPrefix::split(&path_clean) {
+        Some((WindowsPrefix::VerbatimDisk(_) | WindowsPrefix::VerbatimUnc(..), rest))
+            if !is_literal_on_unix(rest) =>
+        {
+            return Err(Error::NotTranslatable(PathBuf::from(path)))
+        }
+        Some((WindowsPrefix::Disk(drive) | WindowsPrefix::VerbatimDisk(drive), rest))
+            if rest.starts_with('\\') =>
+        {
+            url.push('/');
+            url.push(char::from(drive));
+            url.push(':');
+            encode(&rest.replace('\\', "/"), &mut url);
+        }
+        Some((WindowsPrefix::VerbatimDisk(drive), "")) => {
+            url.push('/');
+            url.push(char::from(drive));
+            url.push_str(":/");
+        }
+        Some((
+            WindowsPrefix::Unc(server, share) | WindowsPrefix::VerbatimUnc(server, share),
+            rest,
+        )) if !share.is_empty() => {
+            encode(server, &mut url);
+            url.push('/');
+            encode(share, &mut url);
+            encode(&rest.replace('\\', "/"), &mut url);
+        }
+        Some((WindowsPrefix::Disk(_), _)) | None => {
+            return Err(Error::NotAbsolute(PathBuf::from(path)))
+        }
+        Some(_) => return Err(Error::NotTranslatable(PathBuf::from(path))),
+    }
+    Ok(url)
+}
+
+/// Convert `file:` URL to path according to rules of host operating system
+///
+/// # Arguments
+///
+/// * `url` - URL
+pub fn file_url_to_path(url: &str) -> Result<PathBuf> {
+    if cfg!(target_os = "windows") {
+        file_url_to_windows(url).map(PathBuf::from)
+    } else {
+        file_url_to_unix(url).map(PathBuf::from)
+    }
+}
+
+/// Convert `file:` URL to cleaned Unix path
+///
+/// Fails if the URL names a host other than `localhost`.
+///
+/// ```rust
+/// use joat_path::file_url_to_unix;
+/// assert_eq!(file_url_to_unix("file:///home/me/a%20b/../c").unwrap(), "/home/me/c");
+/// assert_eq!(file_url_to_unix("file://localhost/etc").unwrap(), "/etc");
+/// ```
+pub fn file_url_to_unix(url: &str) -> Result<String> {
+    let (host, path) = split_url(url)?;
+    if !host.is_empty() {
+        return Err(Error::NotTranslatable(PathBuf::from(url)));
+    }
+
+    Ok(clean_unix(&format!("/{}", decode(url, path)?)))
+}
+
+/// Convert `file:` URL to cleaned Windows path
+///
+/// URLs naming a host other than `localhost` produce UNC paths.
+///
+/// ```rust
+/// use joat_path::file_url_to_windows;
+/// assert_eq!(file_url_to_windows("file:///c:/a%20b/../c").unwrap(), "c:\\c");
+/// assert_eq!(file_url_to_windows("file://srv/share/x").unwrap(), "\\\\srv\\share\\x");
+/// ```
+pub fn file_url_to_windows(url: &str) -> Result<String> {
+    let not_translatable = || Error::NotTranslatable(PathBuf::from(url));
+
+    let (host, path) = split_url(url)?;
+    let path = decode(url, path)?.replace('/', "\\");
+    let out = if host.is_empty() {
+        let path = path.trim_start_matches('\\');
+        match path.as_bytes() {
+            [drive, b':' | b'|'] | [drive, b':' | b'|', b'\\', ..]
+                if drive.is_ascii_alphabetic() =>
+            {
+                format!("{}:\\{}", char::from(*drive), &path[2..])
+            }
+            // Legacy form of UNC path with empty authority, e.g. file:////srv/share
+            _ if url_path_starts_with_unc(url) => format!("\\\\{path}"),
+            _ => return Err(not_translatable()),
+        }
+    } else {
+        format!("\\\\{}{path}", decode(url, host)?)
+    };
+
+    let out = clean_windows(&out);
+    match WindowsPrefix::split(&out) {
+        Some((WindowsPrefix::Disk(_), _)) => Ok(out),
+        Some((WindowsPrefix::Unc(_, share), _)) if !share.is_empty() => Ok(out),
+        _ => Err(not_translatable()),
+    }
+}
+
+/// Split URL into host, which is empty for `localhost`, and path
+fn split_url(url: &str) -> Result<(&str, &str)> {
+    let invalid_url = || Error::InvalidUrl(String::from(url));
+
+    let rest = match url.get(..5) {
+        Some(scheme) if scheme.eq_ignore_ascii_case("file:") => &url[5..],
+        _ => return Err(invalid_url()),
+    };
+    let rest = &rest[..rest.find(['?', '#']).unwrap_or(rest.len())];
+
+    let (host, path) = match rest.strip_prefix("//") {
+        Some(rest) => rest.split_at(rest.find('/').unwrap_or(rest.len())),
+        None if rest.starts_with('/') => ("", rest),
+        None => return Err(invalid_url()),
+    };
+
+    if host.eq_ignore_ascii_case("localhost") {
+        Ok(("", path))
+    } else {
+        Ok((host, path))
+    }
+}
+
+fn url_path_starts_with_unc(url: &str) -> bool {
+    split_url(url).is_ok_and(|(host, path)| host.is_empty() && path.starts_with("//"))
+}
+
+const fn is_unreserved(b: u8) -> bool {
+    b.is_ascii_alphanumeric()
+        || matches!(
+            b,
+            b'-' | b'.'
+                | b'_'
+                | b'~'
+                | b'!'
+                | b'$'
+                | b'&'
+                | b'\''
+                | b'('
+                | b')'
+                | b'*'
+                | b'+'
+                | b','
+                | b';'
+                | b'='
+                | b':'
+                | b'@'
+                | b'/'
+        )
+}
+
+fn encode(s: &str, out: &mut String) {
+    for b in s.bytes() {
+        if is_unreserved(b) {
+            out.push(char::from(b));
+        } else {
+            write!(out, "%{b:02X}").expect("writing to string cannot fail");
+        }
+    }
+}
+
+fn decode(url: &str, s: &str) -> Result<String> {
+    let invalid_url = || Error::InvalidUrl(String::from(url));
+
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = s.get(i + 1..i + 3).ok_or_else(invalid_url)?;
+            out.push(u8::from_str_radix(hex, 16).map_err(|_| invalid_url())?);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(out).map_err(|_| Error::NotUnicode(PathBuf::from(url)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{file_url_to_unix, file_url_to_windows, unix_to_file_url, windows_to_file_url};
+    use crate::error::Error;
+    use crate::path_clean::{clean_unix, clean_windows};
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_unix_to_file_url() {
+        let tests = vec![
+            ("/", "file:///"),
+            ("/home/me", "file:///home/me"),
+            ("/home//me/./x/..", "file:///home/me"),
+            ("/a b/100%/c?d#e", "file:///a%20b/100%25/c%3Fd%23e"),
+            ("/caf\u{e9}", "file:///caf%C3%A9"),
+            ("/C:\\x", "file:///C:%5Cx"),
+        ];
+
+        for test in tests {
+            assert_eq!(unix_to_file_url(test.0).unwrap(), test.1);
+        }
+        assert!(matches!(
+            unix_to_file_url("home/me"),
+            Err(Error::NotAbsolute(_))
+        ));
+    }
+
+    #[test]
+    fn test_windows_to_file_url() {
+        let tests = vec![
+            ("C:\\", "file:///C:/"),
+            ("C:\\x\\y", "file:///C:/x/y"),
+            ("c:/x/../y z", "file:///c:/y%20z"),
+            ("\\\\srv\\share", "file://srv/share/"),
+            ("\\\\srv\\share\\x\\..\\y", "file://srv/share/y"),
+            ("\\\\?\\C:\\x", "file:///C:/x"),
+            ("\\\\?\\UNC\\srv\\share\\x", "file://srv/share/x"),
+        ];
+
+        for test in tests {
+            assert_eq!(windows_to_file_url(test.0).unwrap(), test.1);
+        }
+        for path in ["C:x", "\\x", "x\\y"] {
+            assert!(matches!(
+                windows_to_file_url(path),
+                Err(Error::NotAbsolute(_))
+            ));
+        }
+        assert!(windows_to_file_url("\\\\.\\PIPE\\x").is_err());
+        for path in [
+            "\\\\?\\C:\\a\\..\\b",
+            "\\\\?\\C:\\a\\.",
+            "\\\\?\\C:\\a/b",
+            "\\\\?\\UNC\\srv\\share\\..\\x",
+        ] {
+            assert!(matches!(
+                windows_to_file_url(path),
+                Err(Error::NotTranslatable(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_file_url_to_unix() {
+        let tests = vec![
+            ("file:///", "/"),
+            ("file://", "/"),
+            ("file:/home/me", "/home/me"),
+            ("FILE:///home/me/", "/home/me"),
+            ("file://localhost/home/me", "/home/me"),
+            ("file:///a%20b/../c%2fd", "/c/d"),
+            ("file:///x?query#fragment", "/x"),
+            ("file:///caf%c3%a9", "/caf\u{e9}"),
+        ];
+
+        for test in tests {
+            assert_eq!(file_url_to_unix(test.0).unwrap(), test.1);
+        }
+    }
+
+    #[test]
+    fn test_file_url_to_unix_fails() {
+        assert!(matches!(
+            file_url_to_unix("file://srv/share"),
+            Err(Error::NotTranslatable(_))
+        ));
+        for url in ["http:///x", "file:x", "/x", "file:///%zz", "file:///%4"] {
+            assert!(matches!(file_url_to_unix(url), Err(Error::InvalidUrl(_))));
+        }
+        assert!(matches!(
+            file_url_to_unix("file:///%ff"),
+            Err(Error::NotUnicode(_))
+        ));
+    }
+
+    #[test]
+    fn test_file_url_to_windows() {
+        let tests = vec![
+            ("file:///C:/", "C:\\"),
+            ("file:///C:", "C:\\"),
+            ("file:///C:/x/./y", "C:\\x\\y"),
+            ("file:///c|/x", "c:\\x"),
+            ("file://localhost/C:/x", "C:\\x"),
+            ("file://srv/share/x", "\\\\srv\\share\\x"),
+            ("file:////srv/share/x", "\\\\srv\\share\\x"),
+            ("file:///C:/a%20b/..%5C..", "C:\\"),
+        ];
+
+        for test in tests {
+            assert_eq!(file_url_to_windows(test.0).unwrap(), test.1);
+        }
+    }
+
+    #[test]
+    fn test_file_url_to_windows_fails() {
+        for url in ["file:///x", "file:///", "file://srv", "file:///C:x"] {
+            assert!(matches!(
+                file_url_to_windows(url),
+                Err(Error::NotTranslatable(_))
+            ));
+        }
+        assert!(file_url_to_windows("file:///C:/%").is_err());
+    }
+
+    fn segments(exclude: &'static str) -> impl Strategy<Value = Vec<String>> {
+        proptest::collection::vec(
+            proptest::string::string_regex(exclude).expect("valid regex"),
+            0..6,
+        )
+    }
+
+    proptest! {
+        #[test]
+        fn prop_unix_round_trip(segments in segments("[^/\\x00]{0,6}")) {
+            let path = format!("/{}", segments.join("/"));
+            let url = unix_to_file_url(&path).unwrap();
+            prop_assert_eq!(file_url_to_unix(&url).unwrap(), clean_unix(&path));
+        }
+
+        #[test]
+        fn prop_windows_drive_round_trip(
+            drive in "[A-Za-z]",
+            segments in segments("[^/\\\\\\x00]{0,6}"),
+        ) {
+            let path = format!("{drive}:\\{}", segments.join("\\"));
+            let url = windows_to_file_url(&path).unwrap();
+            prop_assert_eq!(file_url_to_windows(&url).unwrap(), clean_windows(&path));
+        }
+
+        #[test]
+        fn prop_windows_unc_round_trip(
+            server in "[A-Za-z0-9][A-Za-z0-9.-]{0,8}",
+            share in "[A-Za-z0-9$][A-Za-z0-9 $]{0,8}",
+            segments in segments("[^/\\\\\\x00]{0,6}"),
+        ) {
+            let path = format!("\\\\{server}\\{share}\\{}", segments.join("\\"));
+            let url = windows_to_file_url(&path).unwrap();
+            prop_assert_eq!(file_url_to_windows(&url).unwrap(), clean_windows(&path));
+        }
+    }
+}
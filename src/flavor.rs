@@ -0,0 +1,175 @@
+// Copyright (c) 2020-3 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+use crate::case_fold::fold_case;
+use crate::windows_prefix::WindowsPrefix;
+
+/// Kind of prefix preceding the body of a path
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PrefixKind {
+    /// Drive letter such as `C:` which may be followed by a rooted or
+    /// relative path
+    Drive,
+
+    /// Prefix such as UNC share `\\server\share` or device `\\.\PIPE` which
+    /// is always rooted and cannot be removed by `..` elements
+    Root,
+
+    /// Verbatim prefix such as `\\?\C:` which disables normalization of the
+    /// entire path
+    Verbatim,
+}
+
+/// Prefix preceding the body of a path
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PathPrefix<'a> {
+    /// Kind of prefix
+    pub kind: PrefixKind,
+
+    /// Text of prefix exactly as it appears in the path
+    pub text: &'a str,
+}
+
+/// Lexical characteristics of a path dialect
+///
+/// Implement this trait to clean paths belonging to a custom dialect
+/// with [`crate::clean_with`]. Separators must be ASCII characters: a
+/// non-ASCII canonical separator is rejected at compile time and
+/// non-ASCII characters are never treated as separators.
+///
+/// ```rust
+/// use joat_path::{clean_with, PathFlavor};
+///
+/// struct ZipEntryFlavor;
+///
+/// impl PathFlavor for ZipEntryFlavor {
+///     const CANONICAL_SEPARATOR: char = '/';
+///
+///     fn is_separator(c: char) -> bool {
+///         c == '/'
+///     }
+///
+///     fn is_rooted(_path: &str) -> bool {
+///         true
+///     }
+/// }
+///
+/// assert_eq!(clean_with::<ZipEntryFlavor>("../../etc/passwd"), "/etc/passwd");
+/// ```
+///
+/// ```compile_fail
+/// use joat_path::{clean_with, PathFlavor};
+///
+/// struct AccentFlavor;
+///
+/// impl PathFlavor for AccentFlavor {
+///     const CANONICAL_SEPARATOR: char = 'é';
+///
+///     fn is_separator(c: char) -> bool {
+///         c == '/'
+///     }
+/// }
+///
+/// clean_with::<AccentFlavor>("/a/b");
+/// ```
+pub trait PathFlavor {
+    /// Primary separator character for this flavour of path
+    const CANONICAL_SEPARATOR: char;
+
+    /// Returns true if character is any valid separator character, false otherwise
+    fn is_separator(c: char) -> bool;
+
+    /// Returns true if path following any prefix is rooted, false otherwise
+    fn is_rooted(path: &str) -> bool {
+        path.starts_with(Self::is_separator)
+    }
+
+    /// Split path into prefix (e.g. drive letter or UNC share) and remainder
+    #[must_use]
+    fn split_prefix(path: &str) -> (Option<PathPrefix<'_>>, &str) {
+        (None, path)
+    }
+
+    /// Returns true if path segments name the same file or directory,
+    /// false otherwise
+    #[must_use]
+    fn is_same_segment(a: &str, b: &str) -> bool {
+        a == b
+    }
+}
+
+/// Unix-style paths
+/// * Path separator is always a single forward slash "/"
+pub struct UnixFlavor;
+
+impl PathFlavor for UnixFlavor {
+    const CANONICAL_SEPARATOR: char = '/';
+
+    fn is_separator(c: char) -> bool {
+        c == '/'
+    }
+}
+
+/// Windows-style paths
+/// * Path separator can be a single forward slash "/" or backslash "\\"
+/// * Path may begin with a drive letter prefix such as `C:`
+/// * Path may begin with a UNC share prefix such as `\\server\share`
+/// * Path may begin with a device prefix such as `\\.\PIPE`
+/// * Path may begin with a verbatim prefix such as `\\?\C:`, in which case
+///   the path is not normalized
+/// * Path segments are compared case-insensitively
+pub struct WindowsFlavor;
+
+impl PathFlavor for WindowsFlavor {
+    const CANONICAL_SEPARATOR: char = '\\';
+
+    fn is_separator(c: char) -> bool {
+        c == '\\' || c == '/'
+    }
+
+    fn split_prefix(path: &str) -> (Option<PathPrefix<'_>>, &str) {
+        let Some((prefix, rest)) = WindowsPrefix::split(path) else {
+            return (None, path);
+        };
+
+        let kind = match prefix {
+            WindowsPrefix::Disk(_) => PrefixKind::Drive,
+            WindowsPrefix::Unc(..) | WindowsPrefix::DeviceNs(_) => PrefixKind::Root,
+            _ => PrefixKind::Verbatim,
+        };
+
+        let text = &path[..path.len() - rest.len()];
+        (Some(PathPrefix { kind, text }), rest)
+    }
+
+    fn is_same_segment(a: &str, b: &str) -> bool {
+        a.chars().map(fold_case).eq(b.chars().map(fold_case))
+    }
+}
+
+/// Path flavour of host operating system
+#[cfg(not(target_os = "windows"))]
+pub type PlatformFlavor = UnixFlavor;
+
+/// Path flavour of host operating system
+#[cfg(target_os = "windows")]
+pub type PlatformFlavor = WindowsFlavor;
@@ -0,0 +1,337 @@
+// Copyright (c) 2020-3 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+use crate::absolute_path::absolute_path;
+use crate::absolute_path_buf::AbsolutePathBuf;
+use crate::error::{Error, Result};
+use crate::path_clean::clean_os;
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+/// Source of home directories used to expand `~` and `~user`
+pub trait HomeProvider {
+    /// Home directory of current user, if known
+    fn home_dir(&self) -> Option<PathBuf>;
+
+    /// Home directory of named user, if known
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - User name
+    fn user_home_dir(&self, user: &str) -> Option<PathBuf>;
+}
+
+/// Home directories of the host system: the current user's home directory
+/// is taken from the environment and, on Unix, other users' home
+/// directories are read from `/etc/passwd`
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemHomeProvider;
+
+impl HomeProvider for SystemHomeProvider {
+    fn home_dir(&self) -> Option<PathBuf> {
+        std::env::home_dir().filter(|p| !p.as_os_str().is_empty())
+    }
+
+    fn user_home_dir(&self, user: &str) -> Option<PathBuf> {
+        if cfg!(target_os = "windows") {
+            None
+        } else {
+            passwd_home_dir(&std::fs::read_to_string("/etc/passwd").ok()?, user)
+        }
+    }
+}
+
+/// Fixed set of home directories, e.g. for testing
+///
+/// ```rust
+/// use joat_path::{expand_home, MapHomeProvider};
+/// use std::path::Path;
+///
+/// let homes = MapHomeProvider::new()
+///     .home_dir("/home/me")
+///     .user_home_dir("you", "/home/you");
+/// assert_eq!(expand_home("~/aa", &homes).unwrap(), Path::new("/home/me/aa"));
+/// assert_eq!(expand_home("~you/aa", &homes).unwrap(), Path::new("/home/you/aa"));
+/// assert!(expand_home("~them/aa", &homes).is_err());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct MapHomeProvider {
+    home_dir: Option<PathBuf>,
+    user_home_dirs: HashMap<String, PathBuf>,
+}
+
+impl MapHomeProvider {
+    /// Provider with no home directories
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set home directory of current user
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - Home directory
+    #[must_use]
+    pub fn home_dir<P: Into<PathBuf>>(mut self, dir: P) -> Self {
+        self.home_dir = Some(dir.into());
+        self
+    }
+
+    /// Set home directory of named user
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - User name
+    /// * `dir` - Home directory
+    #[must_use]
+    pub fn user_home_dir<P: Into<PathBuf>>(mut self, user: &str, dir: P) -> Self {
+        self.user_home_dirs.insert(String::from(user), dir.into());
+        self
+    }
+}
+
+impl HomeProvider for MapHomeProvider {
+    fn home_dir(&self) -> Option<PathBuf> {
+        self.home_dir.clone()
+    }
+
+    fn user_home_dir(&self, user: &str) -> Option<PathBuf> {
+        self.user_home_dirs.get(user).cloned()
+    }
+}
+
+/// Expand leading `~` or `~user` element of path to home directory
+///
+/// Paths that do not begin with `~` are returned unchanged. Fails if the
+/// home directory is not known.
+///
+/// # Arguments
+///
+/// * `path` - Path
+/// * `homes` - Source of home directories
+pub fn expand_home<P: AsRef<Path>, H: HomeProvider + ?Sized>(
+    path: P,
+    homes: &H,
+) -> Result<PathBuf> {
+    let path = path.as_ref();
+    let mut components = path.components();
+    let Some(Component::Normal(first)) = components.next() else {
+        return Ok(path.to_path_buf());
+    };
+    if !first.as_encoded_bytes().starts_with(b"~") {
+        return Ok(path.to_path_buf());
+    }
+
+    let first = first
+        .to_str()
+        .ok_or_else(|| Error::NotUnicode(path.to_path_buf()))?;
+    let home_dir = match &first[1..] {
+        "" => homes.home_dir(),
+        user => homes.user_home_dir(user),
+    }
+    .ok_or_else(|| Error::HomeNotFound(String::from(first)))?;
+
+    let rest = components.as_path();
+    if rest.as_os_str().is_empty() {
+        Ok(home_dir)
+    } else {
+        Ok(home_dir.join(rest))
+    }
+}
+
+/// Normalize a target path to an absolute path relative to a base
+/// directory after expanding a leading `~` or `~user` element
+///
+/// # Arguments
+///
+/// * `base_dir` - Base directory (must be absolute), typically the current working directory
+/// * `path` - Path
+/// * `homes` - Source of home directories
+pub fn absolute_path_expanded<B: AsRef<Path>, P: AsRef<Path>, H: HomeProvider + ?Sized>(
+    base_dir: B,
+    path: P,
+    homes: &H,
+) -> Result<AbsolutePathBuf> {
+    absolute_path(base_dir, expand_home(path, homes)?)
+}
+
+/// Abbreviate path within current user's home directory to begin with `~`
+/// for display
+///
+/// Path is cleaned before comparison so that e.g. `/home/me/../x` is not
+/// abbreviated. Paths outside the home directory are returned unchanged.
+///
+/// # Arguments
+///
+/// * `path` - Path
+/// * `homes` - Source of home directories
+pub fn abbreviate_home<P: AsRef<Path>, H: HomeProvider + ?Sized>(path: P, homes: &H) -> PathBuf {
+    let path = path.as_ref();
+    let path_clean = PathBuf::from(clean_os(path.as_os_str()));
+    let rest = homes
+        .home_dir()
+        .map(|home_dir| PathBuf::from(clean_os(home_dir.as_os_str())))
+        .filter(|home_dir| home_dir.parent().is_some())
+        .and_then(|home_dir| {
+            path_clean
+                .strip_prefix(home_dir)
+                .ok()
+                .map(Path::to_path_buf)
+        });
+    match rest {
+        Some(rest) if rest.as_os_str().is_empty() => PathBuf::from("~"),
+        Some(rest) => Path::new("~").join(rest),
+        None => path.to_path_buf(),
+    }
+}
+
+fn passwd_home_dir(passwd: &str, user: &str) -> Option<PathBuf> {
+    passwd.lines().find_map(|line| {
+        let fields = line.split(':').collect::<Vec<_>>();
+        match fields.as_slice() {
+            [name, _, _, _, _, home_dir, ..] if *name == user && !home_dir.is_empty() => {
+                Some(PathBuf::from(home_dir))
+            }
+            _ => None,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        abbreviate_home, absolute_path_expanded, expand_home, passwd_home_dir, MapHomeProvider,
+    };
+    use crate::error::Error;
+    use crate::test_helpers::abs;
+    use std::path::{Path, PathBuf};
+
+    fn homes() -> MapHomeProvider {
+        MapHomeProvider::new()
+            .home_dir(abs("/home/me"))
+            .user_home_dir("you", abs("/home/you"))
+    }
+
+    #[test]
+    fn test_expand_home() {
+        let homes = homes();
+        assert_eq!(expand_home("~", &homes).unwrap(), abs("/home/me"));
+        assert_eq!(expand_home("~/", &homes).unwrap(), abs("/home/me"));
+        assert_eq!(
+            expand_home("~/aa/../bb", &homes).unwrap(),
+            abs("/home/me").join("aa").join("..").join("bb")
+        );
+        assert_eq!(
+            expand_home("~you/aa", &homes).unwrap(),
+            abs("/home/you").join("aa")
+        );
+    }
+
+    #[test]
+    fn test_expand_home_unchanged() {
+        let homes = homes();
+        for path in ["aa/~", "./~", "", "aa~"] {
+            assert_eq!(expand_home(path, &homes).unwrap(), Path::new(path));
+        }
+        assert_eq!(expand_home(abs("/~"), &homes).unwrap(), abs("/~"));
+    }
+
+    #[test]
+    fn test_expand_home_fails() {
+        assert!(matches!(
+            expand_home("~them/aa", &homes()),
+            Err(Error::HomeNotFound(user)) if user == "~them"
+        ));
+        assert!(matches!(
+            expand_home("~/aa", &MapHomeProvider::new()),
+            Err(Error::HomeNotFound(user)) if user == "~"
+        ));
+    }
+
+    #[test]
+    fn test_absolute_path_expanded() {
+        let homes = homes();
+        assert_eq!(
+            absolute_path_expanded(abs("/base"), "~/aa/../bb", &homes).unwrap(),
+            abs("/home/me/bb")
+        );
+        assert_eq!(
+            absolute_path_expanded(abs("/base"), "aa/~", &homes).unwrap(),
+            abs("/base/aa/~")
+        );
+        assert!(absolute_path_expanded(abs("/base"), "~them", &homes).is_err());
+    }
+
+    #[test]
+    fn test_abbreviate_home() {
+        let homes = homes();
+        assert_eq!(abbreviate_home(abs("/home/me"), &homes), Path::new("~"));
+        assert_eq!(
+            abbreviate_home(abs("/home/me/aa/bb"), &homes),
+            Path::new("~").join("aa").join("bb")
+        );
+        assert_eq!(abbreviate_home(abs("/home/mee"), &homes), abs("/home/mee"));
+        assert_eq!(abbreviate_home(abs("/home/you"), &homes), abs("/home/you"));
+        assert_eq!(
+            abbreviate_home(abs("/home/me/../x"), &homes),
+            abs("/home/me/../x")
+        );
+        assert_eq!(
+            abbreviate_home(abs("/home/me/./aa/../bb"), &homes),
+            Path::new("~").join("bb")
+        );
+        assert_eq!(
+            abbreviate_home(abs("/home/you/../me/aa"), &homes),
+            Path::new("~").join("aa")
+        );
+        assert_eq!(
+            abbreviate_home(
+                abs("/home/me/aa"),
+                &MapHomeProvider::new().home_dir(abs("/home/me/"))
+            ),
+            Path::new("~").join("aa")
+        );
+        assert_eq!(
+            abbreviate_home(abs("/aa"), &MapHomeProvider::new().home_dir(abs("/"))),
+            abs("/aa")
+        );
+    }
+
+    #[test]
+    fn test_passwd_home_dir() {
+        let passwd = "\
+root:x:0:0:root:/root:/bin/bash
+me:x:1000:1000:Me,,,:/home/me:/bin/bash
+nohome:x:1001:1001:::/bin/sh
+";
+        assert_eq!(
+            passwd_home_dir(passwd, "me"),
+            Some(PathBuf::from("/home/me"))
+        );
+        assert_eq!(
+            passwd_home_dir(passwd, "root"),
+            Some(PathBuf::from("/root"))
+        );
+        assert_eq!(passwd_home_dir(passwd, "nohome"), None);
+        assert_eq!(passwd_home_dir(passwd, "them"), None);
+    }
+}
@@ -32,7 +32,68 @@
 #![allow(clippy::module_name_repetitions)]
 #![allow(clippy::option_if_let_else)]
 mod absolute_path;
+mod absolute_path_buf;
+mod canonicalize;
+mod case_fold;
+mod error;
+mod expand_vars;
+mod file_url;
+mod flavor;
+mod home;
 mod path_clean;
+mod relative_path;
+mod relative_path_buf;
+mod safe_join;
+#[cfg(test)]
+mod test_helpers;
+mod translate;
+mod validate_windows;
+mod windows_cwd;
+mod windows_path_key;
+mod windows_prefix;
+pub mod wsl;
 
-pub use self::absolute_path::absolute_path;
-pub use self::path_clean::{clean, clean_unix, clean_windows, PathClean};
+pub use self::absolute_path::{
+    absolute_path, absolute_path_unix, absolute_path_windows, absolute_path_windows_with_drives,
+    absolute_path_with,
+};
+pub use self::absolute_path_buf::{AbsolutePath, AbsolutePathBuf};
+pub use self::canonicalize::{
+    canonicalize_with, canonicalize_with_limit, resolve_in_root, resolve_in_root_with, FileKind,
+    MemoryVfs, OsVfs, Vfs, DEFAULT_MAX_SYMLINKS,
+};
+pub use self::error::{Error, Result};
+pub use self::expand_vars::{
+    expand_vars, expand_vars_unix, expand_vars_windows, ExpandMode, MapVarSource, SystemVarSource,
+    VarSource,
+};
+pub use self::file_url::{
+    file_url_to_path, file_url_to_unix, file_url_to_windows, path_to_file_url, unix_to_file_url,
+    windows_to_file_url,
+};
+pub use self::flavor::{
+    PathFlavor, PathPrefix, PlatformFlavor, PrefixKind, UnixFlavor, WindowsFlavor,
+};
+pub use self::home::{
+    abbreviate_home, absolute_path_expanded, expand_home, HomeProvider, MapHomeProvider,
+    SystemHomeProvider,
+};
+pub use self::path_clean::{
+    clean, clean_bytes_with, clean_cow, clean_os, clean_os_with, clean_unix, clean_unix_cow,
+    clean_unix_with_options, clean_wide_with, clean_windows, clean_windows_cow,
+    clean_windows_with_options, clean_with, clean_with_cow, clean_with_options, CleanOptions,
+    PathClean,
+};
+pub use self::relative_path::{
+    relative_path, relative_path_unix, relative_path_windows, relative_path_with,
+};
+pub use self::relative_path_buf::RelativePathBuf;
+pub use self::safe_join::{safe_join, safe_join_unix, safe_join_windows, safe_join_with};
+pub use self::translate::{to_unix, to_windows, DriveMapping};
+pub use self::validate_windows::{
+    validate_windows_component, validate_windows_path, WindowsNameError, WindowsPathError,
+    MAX_COMPONENT_LEN, MAX_PATH_LEN, MAX_VERBATIM_PATH_LEN,
+};
+pub use self::windows_cwd::WindowsCwd;
+pub use self::windows_path_key::WindowsPathKey;
+pub use self::windows_prefix::WindowsPrefix;
@@ -0,0 +1,462 @@
+// Copyright (c) 2020-3 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+#![warn(clippy::all)]
+#![warn(clippy::cargo)]
+#![warn(clippy::nursery)]
+#![warn(clippy::pedantic)]
+#![allow(clippy::derive_partial_eq_without_eq)]
+#![allow(clippy::enum_glob_use)]
+#![allow(clippy::match_wildcard_for_single_variants)]
+#![allow(clippy::missing_errors_doc)]
+#![allow(clippy::module_name_repetitions)]
+#![allow(clippy::option_if_let_else)]
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use joat_path::{
+    absolute_path, absolute_path_unix, absolute_path_windows, clean_unix, clean_windows,
+    relative_path_unix, relative_path_windows, to_unix, to_windows, DriveMapping, Error,
+};
+use std::fmt::Write as _;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Flavor {
+    Unix,
+    Windows,
+}
+
+impl Flavor {
+    const HOST: Self = if cfg!(target_os = "windows") {
+        Self::Windows
+    } else {
+        Self::Unix
+    };
+}
+
+/// Result of processing one input path
+struct Output {
+    input: String,
+    result: Result<String, String>,
+}
+
+fn main() -> ExitCode {
+    let matches = cli().get_matches();
+    let mut stdin = Vec::new();
+    if matches
+        .subcommand()
+        .is_some_and(|(_, m)| m.get_flag("null"))
+    {
+        if let Err(e) = std::io::stdin().read_to_end(&mut stdin) {
+            eprintln!("joat-path: {e}");
+            return ExitCode::FAILURE;
+        }
+    }
+
+    let mut stdout = std::io::stdout().lock();
+    let mut stderr = std::io::stderr().lock();
+    match run(&matches, &stdin, &mut stdout, &mut stderr) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            _ = writeln!(stderr, "joat-path: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn cli() -> Command {
+    let common_args = [
+        Arg::new("flavor")
+            .long("flavor")
+            .value_name("FLAVOR")
+            .value_parser(["unix", "windows"])
+            .help("Path flavour [default: flavour of host operating system]"),
+        Arg::new("null")
+            .short('0')
+            .long("null")
+            .action(ArgAction::SetTrue)
+            .conflicts_with("paths")
+            .help("Read NUL-delimited paths from stdin and write NUL-delimited output"),
+        Arg::new("json")
+            .long("json")
+            .action(ArgAction::SetTrue)
+            .help("Write output as JSON"),
+        Arg::new("paths")
+            .value_name("PATH")
+            .num_args(1..)
+            .required_unless_present("null")
+            .help("Paths"),
+    ];
+
+    Command::new("joat-path")
+        .about("Lexical path manipulation")
+        .version(env!("CARGO_PKG_VERSION"))
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .subcommand(
+            Command::new("clean")
+                .about("Clean paths lexically")
+                .args(common_args.clone()),
+        )
+        .subcommand(
+            Command::new("absolute")
+                .about("Make paths absolute without accessing the file system")
+                .arg(
+                    Arg::new("base")
+                        .long("base")
+                        .value_name("DIR")
+                        .help("Base directory [default: current directory]"),
+                )
+                .args(common_args.clone()),
+        )
+        .subcommand(
+            Command::new("relative")
+                .about("Compute paths relative to a directory")
+                .arg(
+                    Arg::new("from").long("from").value_name("DIR").help(
+                        "Directory from which paths are relative [default: current directory]",
+                    ),
+                )
+                .args(common_args.clone()),
+        )
+        .subcommand(
+            Command::new("convert")
+                .about("Convert paths of the given flavour to the other flavour")
+                .arg(
+                    Arg::new("mapping")
+                        .long("mapping")
+                        .value_name("MAPPING")
+                        .value_parser(["wsl", "msys", "cygwin"])
+                        .default_value("wsl")
+                        .help("Mapping of drives to Unix directories"),
+                )
+                .args(common_args),
+        )
+}
+
+/// Run subcommand, returning false if any path could not be processed
+fn run<O: Write, E: Write>(
+    matches: &ArgMatches,
+    stdin: &[u8],
+    stdout: &mut O,
+    stderr: &mut E,
+) -> std::io::Result<bool> {
+    let Some((command, matches)) = matches.subcommand() else {
+        return Ok(false);
+    };
+
+    let flavor = match matches.get_one::<String>("flavor").map(String::as_str) {
+        Some("unix") => Flavor::Unix,
+        Some("windows") => Flavor::Windows,
+        _ => Flavor::HOST,
+    };
+    let null = matches.get_flag("null");
+
+    let inputs = if null {
+        split_nul(stdin)
+    } else {
+        matches
+            .get_many::<String>("paths")
+            .into_iter()
+            .flatten()
+            .map(|s| Ok(s.clone()))
+            .collect()
+    };
+
+    let process = |path: &str| -> Result<String, Error> {
+        match command {
+            "clean" => Ok(match flavor {
+                Flavor::Unix => clean_unix(path),
+                Flavor::Windows => clean_windows(path),
+            }),
+            "absolute" => absolute(flavor, matches.get_one::<String>("base"), path),
+            "relative" => {
+                let from = dir_or_current(matches.get_one::<String>("from"))?;
+                match flavor {
+                    Flavor::Unix => relative_path_unix(&from, path),
+                    Flavor::Windows => relative_path_windows(&from, path),
+                }
+            }
+            "convert" => {
+                let mapping = match matches.get_one::<String>("mapping").map(String::as_str) {
+                    Some("msys") => DriveMapping::msys(),
+                    Some("cygwin") => DriveMapping::cygwin(),
+                    _ => DriveMapping::wsl(),
+                };
+                match flavor {
+                    Flavor::Unix => to_windows(path, &mapping),
+                    Flavor::Windows => to_unix(path, &mapping),
+                }
+            }
+            _ => unreachable!("unknown subcommand {command}"),
+        }
+    };
+
+    let outputs = inputs
+        .into_iter()
+        .map(|input| match input {
+            Ok(input) => Output {
+                result: process(&input).map_err(|e| e.to_string()),
+                input,
+            },
+            Err(input) => Output {
+                result: Err(Error::NotUnicode(PathBuf::from(&input)).to_string()),
+                input,
+            },
+        })
+        .collect::<Vec<_>>();
+
+    if matches.get_flag("json") {
+        write_json(stdout, &outputs)?;
+    } else {
+        let terminator = if null { '\0' } else { '\n' };
+        for output in &outputs {
+            match &output.result {
+                Ok(s) => write!(stdout, "{s}{terminator}")?,
+                Err(e) => writeln!(stderr, "joat-path: {e}")?,
+            }
+        }
+    }
+
+    Ok(outputs.iter().all(|output| output.result.is_ok()))
+}
+
+/// Split NUL-delimited input into strings, returning lossy conversion of
+/// any input that is not valid UTF-8 as an error
+fn split_nul(bytes: &[u8]) -> Vec<Result<String, String>> {
+    let bytes = bytes.strip_suffix(b"\0").unwrap_or(bytes);
+    if bytes.is_empty() {
+        return Vec::new();
+    }
+
+    bytes
+        .split(|b| *b == 0)
+        .map(|s| String::from_utf8(s.to_vec()).map_err(|_| String::from_utf8_lossy(s).into_owned()))
+        .collect()
+}
+
+fn dir_or_current(dir: Option<&String>) -> Result<String, Error> {
+    if let Some(dir) = dir {
+        return Ok(dir.clone());
+    }
+
+    let dir = std::env::current_dir().map_err(Error::Io)?;
+    dir.to_str().map(String::from).ok_or(Error::NotUnicode(dir))
+}
+
+fn absolute(flavor: Flavor, base: Option<&String>, path: &str) -> Result<String, Error> {
+    let base = dir_or_current(base)?;
+    match flavor {
+        _ if flavor == Flavor::HOST => absolute_path(&base, path).and_then(|p| {
+            p.to_str()
+                .map(String::from)
+                .ok_or_else(|| Error::NotUnicode(p.into_path_buf()))
+        }),
+        Flavor::Unix => absolute_path_unix(&base, path),
+        Flavor::Windows => absolute_path_windows(&base, path),
+    }
+}
+
+fn write_json<W: Write>(out: &mut W, outputs: &[Output]) -> std::io::Result<()> {
+    writeln!(out, "[")?;
+    for (i, output) in outputs.iter().enumerate() {
+        let (key, value) = match &output.result {
+            Ok(s) => ("output", s),
+            Err(e) => ("error", e),
+        };
+        writeln!(
+            out,
+            "  {{\"input\": {}, \"{key}\": {}}}{}",
+            json_string(&output.input),
+            json_string(value),
+            if i + 1 < outputs.len() { "," } else { "" }
+        )?;
+    }
+    writeln!(out, "]")
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if u32::from(c) < 0x20 => {
+                write!(out, "\\u{:04x}", u32::from(c)).expect("writing to string cannot fail");
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cli, json_string, run, split_nul};
+
+    fn run_args(args: &[&str], stdin: &[u8]) -> (bool, String, String) {
+        let matches = cli()
+            .try_get_matches_from(std::iter::once("joat-path").chain(args.iter().copied()))
+            .unwrap();
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        let ok = run(&matches, stdin, &mut stdout, &mut stderr).unwrap();
+        (
+            ok,
+            String::from_utf8(stdout).unwrap(),
+            String::from_utf8(stderr).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_clean() {
+        assert_eq!(
+            run_args(&["clean", "--flavor", "unix", "a/../b", "/c/./d/"], b""),
+            (true, String::from("b\n/c/d\n"), String::new())
+        );
+        assert_eq!(
+            run_args(&["clean", "--flavor", "windows", "C:/a/../b"], b""),
+            (true, String::from("C:\\b\n"), String::new())
+        );
+    }
+
+    #[test]
+    fn test_absolute() {
+        assert_eq!(
+            run_args(
+                &["absolute", "--flavor", "unix", "--base", "/a/b", "../c", "/d"],
+                b""
+            ),
+            (true, String::from("/a/c\n/d\n"), String::new())
+        );
+        assert_eq!(
+            run_args(
+                &["absolute", "--flavor", "windows", "--base", "C:\\a", "b", "D:\\c"],
+                b""
+            ),
+            (true, String::from("C:\\a\\b\nD:\\c\n"), String::new())
+        );
+        assert_eq!(
+            run_args(
+                &["absolute", "--flavor", "windows", "--base", "C:\\a", "\\b", "D:c"],
+                b""
+            ),
+            (true, String::from("C:\\b\nD:\\c\n"), String::new())
+        );
+
+        let (ok, stdout, stderr) =
+            run_args(&["absolute", "--flavor", "unix", "--base", "a", "b"], b"");
+        assert!(!ok);
+        assert!(stdout.is_empty());
+        assert!(stderr.contains("not absolute"));
+    }
+
+    #[test]
+    fn test_relative() {
+        assert_eq!(
+            run_args(
+                &["relative", "--flavor", "unix", "--from", "/a/b", "/a/c", "/a/b/d"],
+                b""
+            ),
+            (true, String::from("../c\nd\n"), String::new())
+        );
+    }
+
+    #[test]
+    fn test_convert() {
+        assert_eq!(
+            run_args(&["convert", "--flavor", "windows", "C:\\a"], b""),
+            (true, String::from("/mnt/c/a\n"), String::new())
+        );
+        assert_eq!(
+            run_args(
+                &["convert", "--flavor", "unix", "--mapping", "msys", "/c/a"],
+                b""
+            ),
+            (true, String::from("C:\\a\n"), String::new())
+        );
+    }
+
+    #[test]
+    fn test_null() {
+        assert_eq!(
+            run_args(
+                &["clean", "--flavor", "unix", "-0"],
+                b"a/../b\0c d/\n/./e\0"
+            ),
+            (true, String::from("b\0c d/\n/e\0"), String::new())
+        );
+        assert_eq!(
+            run_args(&["clean", "-0"], b""),
+            (true, String::new(), String::new())
+        );
+        assert!(cli()
+            .try_get_matches_from(["joat-path", "clean", "-0", "a"])
+            .is_err());
+    }
+
+    #[test]
+    fn test_json() {
+        let (ok, stdout, _) = run_args(
+            &[
+                "relative", "--flavor", "unix", "--json", "--from", "/a", "/a/\"b\"", "c",
+            ],
+            b"",
+        );
+        assert!(!ok);
+        assert_eq!(
+            stdout,
+            "[\n  {\"input\": \"/a/\\\"b\\\"\", \"output\": \"\\\"b\\\"\"},\n  {\"input\": \"c\", \"error\": \"Paths /a and c do not share a common root\"}\n]\n"
+        );
+
+        let (ok, stdout, _) = run_args(&["clean", "--json", "-0"], b"a\xff\0");
+        assert!(!ok);
+        assert!(stdout.contains("\"input\": \"a\u{fffd}\", \"error\":"));
+    }
+
+    #[test]
+    fn test_split_nul() {
+        assert!(split_nul(b"").is_empty());
+        assert_eq!(
+            split_nul(b"a\0\0b"),
+            vec![
+                Ok(String::from("a")),
+                Ok(String::new()),
+                Ok(String::from("b"))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_json_string() {
+        assert_eq!(
+            json_string("a\"\\\n\t\u{1}\u{e9}"),
+            "\"a\\\"\\\\\\n\\t\\u0001\u{e9}\""
+        );
+    }
+}
@@ -13,307 +13,421 @@
 //! any symlink resolution or absolute path resolution. For more information you can see ["Getting Dot-Dot
 //! Right"](https://9p.io/sys/doc/lexnames.html).
 //!
-//! For convenience, the [`PathClean`] trait is exposed and comes implemented for [`std::path::PathBuf`].
+//! For convenience, the [`PathClean`] trait is exposed and comes implemented for [`std::path::Path`],
+//! [`std::path::PathBuf`], [`str`], [`String`], [`std::ffi::OsStr`], [`std::ffi::OsString`] and
+//! [`std::borrow::Cow<Path>`].
 //!
 //! ```rust
-//! use std::path::PathBuf;
+//! use std::path::{Path, PathBuf};
 //! use joat_path::{clean, PathClean};
 //! assert_eq!(clean("hello/world/.."), "hello");
 //! assert_eq!(
 //!     PathBuf::from("/test/../path/").clean(),
 //!     PathBuf::from("/path")
 //! );
+//! assert_eq!("a/../b".clean_unix(), "b");
+//! assert_eq!("a/../b".clean_windows(), "b");
+//! assert_eq!(Path::new("/a/./b").clean_windows(), PathBuf::from("\\a\\b"));
 //! ```
-use self::internal::PathCharacteristics;
-use std::path::PathBuf;
+use self::internal::CodeUnit;
+use crate::flavor::{PathFlavor, PlatformFlavor, UnixFlavor, WindowsFlavor};
+use std::borrow::Cow;
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
 
 /// The Clean trait implements a `clean` method. It's recommended you use the provided [`clean`]
 /// function.
 pub trait PathClean<T> {
-    fn clean(&self) -> T;
+    /// Clean path according to rules of path flavour `P`
+    fn clean_with<P: PathFlavor>(&self) -> T;
+
+    /// Clean path according to rules of host operating system
+    fn clean(&self) -> T {
+        self.clean_with::<PlatformFlavor>()
+    }
+
+    /// Clean path according to Unix rules
+    fn clean_unix(&self) -> T {
+        self.clean_with::<UnixFlavor>()
+    }
+
+    /// Clean path according to Windows rules
+    fn clean_windows(&self) -> T {
+        self.clean_with::<WindowsFlavor>()
+    }
 }
 
-/// `PathClean` implemented for `PathBuf`
-impl PathClean<Self> for PathBuf {
-    fn clean(&self) -> Self {
-        Self::from(clean(self.to_str().unwrap_or("")))
+/// `PathClean` implemented for `str`
+impl PathClean<String> for str {
+    fn clean_with<P: PathFlavor>(&self) -> String {
+        clean_with::<P>(self)
     }
 }
 
-mod internal {
-    pub trait PathCharacteristics {
-        /// Primary separator character for this type of path
-        const CANONICAL_SEPARATOR: char;
+/// `PathClean` implemented for `String`
+impl PathClean<Self> for String {
+    fn clean_with<P: PathFlavor>(&self) -> Self {
+        clean_with::<P>(self)
+    }
+}
 
-        /// Returns true if string is any valid separator character, false otherwise
-        fn is_separator(path: &str) -> bool;
+/// `PathClean` implemented for `OsStr`
+impl PathClean<OsString> for OsStr {
+    fn clean_with<P: PathFlavor>(&self) -> OsString {
+        clean_os_with::<P>(self)
+    }
+}
 
-        /// Returns true if string starts with any valid separator character, false otherwise
-        fn starts_with_separator(path: &str) -> bool;
+/// `PathClean` implemented for `OsString`
+impl PathClean<Self> for OsString {
+    fn clean_with<P: PathFlavor>(&self) -> Self {
+        clean_os_with::<P>(self)
+    }
+}
 
-        /// Removes separators from end of string
-        fn trim_end_matches_separator(path: &str) -> &str;
+/// `PathClean` implemented for `Path`
+impl PathClean<PathBuf> for Path {
+    fn clean_with<P: PathFlavor>(&self) -> PathBuf {
+        PathBuf::from(clean_os_with::<P>(self.as_os_str()))
+    }
+}
+
+/// `PathClean` implemented for `PathBuf`
+impl PathClean<Self> for PathBuf {
+    fn clean_with<P: PathFlavor>(&self) -> Self {
+        self.as_path().clean_with::<P>()
+    }
+}
 
-        /// Split path on separators
-        fn split_on_separators(path: &str) -> Vec<&str>;
+/// `PathClean` implemented for `Cow<Path>`
+impl PathClean<PathBuf> for Cow<'_, Path> {
+    fn clean_with<P: PathFlavor>(&self) -> PathBuf {
+        self.as_ref().clean_with::<P>()
     }
+}
 
-    /// Characteristics for Unix-style paths
-    /// * Path separator is always a single forward slash "/"
-    pub struct UnixPath;
+mod internal {
+    use crate::flavor::{PathFlavor, PrefixKind};
+    use std::borrow::Cow;
 
-    impl PathCharacteristics for UnixPath {
-        const CANONICAL_SEPARATOR: char = '/';
+    /// Code unit of an encoded path, e.g. a byte of a UTF-8, WTF-8 or
+    /// arbitrary byte string or a 16-bit unit of a Windows wide string
+    pub trait CodeUnit: Copy + Eq {
+        /// Returns ASCII character if code unit is ASCII
+        fn to_ascii(self) -> Option<u8>;
 
-        fn is_separator(path: &str) -> bool {
-            path == "/"
-        }
+        /// Returns code unit for ASCII character
+        fn from_ascii(b: u8) -> Self;
 
-        fn starts_with_separator(path: &str) -> bool {
-            path.starts_with('/')
+        /// Returns code units as string if they are valid UTF-8
+        fn as_str(units: &[Self]) -> Option<&str>;
+    }
+
+    impl CodeUnit for u8 {
+        fn to_ascii(self) -> Option<u8> {
+            self.is_ascii().then_some(self)
         }
 
-        fn trim_end_matches_separator(path: &str) -> &str {
-            path.trim_end_matches('/')
+        fn from_ascii(b: u8) -> Self {
+            b
         }
 
-        fn split_on_separators(path: &str) -> Vec<&str> {
-            path.split('/').collect()
+        fn as_str(units: &[Self]) -> Option<&str> {
+            std::str::from_utf8(units).ok()
         }
     }
 
-    /// Characteristics for Windows-style paths
-    /// * Path separator can be a single forward slash "/" or backslash "\\"
-    pub struct WindowsPath;
-
-    impl PathCharacteristics for WindowsPath {
-        const CANONICAL_SEPARATOR: char = '\\';
-
-        fn is_separator(path: &str) -> bool {
-            path == "\\" || path == "/"
+    impl CodeUnit for u16 {
+        fn to_ascii(self) -> Option<u8> {
+            u8::try_from(self).ok().filter(u8::is_ascii)
         }
 
-        fn starts_with_separator(path: &str) -> bool {
-            path.starts_with(['\\', '/'])
+        fn from_ascii(b: u8) -> Self {
+            Self::from(b)
         }
 
-        fn trim_end_matches_separator(path: &str) -> &str {
-            path.trim_end_matches(['\\', '/'])
+        fn as_str(_units: &[Self]) -> Option<&str> {
+            None
         }
+    }
 
-        fn split_on_separators(path: &str) -> Vec<&str> {
-            path.split(['\\', '/']).collect()
-        }
+    /// Prefix preceding the body of an encoded path
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct UnitPrefix<'a, U> {
+        pub kind: PrefixKind,
+        pub text: &'a [U],
     }
 
-    /// Get normalized version of special path if path is special
-    ///
-    /// # Arguments
-    ///
-    /// * `path` - Path
-    pub fn special_path<P: PathCharacteristics>(path: &str) -> Option<String> {
-        if P::is_separator(path) {
-            return Some(P::CANONICAL_SEPARATOR.to_string());
-        }
-        match path {
-            "" | "." => Some(String::from(".")),
-            ".." => Some(String::from("..")),
-            _ => None,
+    /// Invoke function with string form of path: if the path is not known
+    /// to be valid UTF-8, code units that are not ASCII are replaced by
+    /// placeholder characters so that byte offsets in string correspond to
+    /// code unit offsets in path
+    fn with_str<U: CodeUnit, R>(path: &[U], text: Option<&str>, f: impl FnOnce(&str) -> R) -> R {
+        if let Some(s) = text.or_else(|| U::as_str(path)) {
+            return f(s);
         }
+
+        let s = path
+            .iter()
+            .map(|u| u.to_ascii().map_or('\u{1a}', char::from))
+            .collect::<String>();
+        f(&s)
     }
 
-    /// Determine if path is rooted
-    ///
-    /// # Arguments
-    ///
-    /// * `path` - Path
-    pub fn is_root<P: PathCharacteristics>(path: &str) -> bool {
-        P::starts_with_separator(path)
+    /// Returns true if code unit is a separator
+    pub fn is_separator<P: PathFlavor, U: CodeUnit>(unit: U) -> bool {
+        unit.to_ascii()
+            .is_some_and(|b| P::is_separator(char::from(b)))
     }
 
-    /// Trim trailing path separators from end of path
-    ///
-    /// # Arguments
-    ///
-    /// * `path` - Path
-    pub fn trim_end_path<P: PathCharacteristics>(path: &str) -> &str {
-        P::trim_end_matches_separator(path)
+    /// Returns true if code units match ASCII string
+    pub fn eq_ascii<U: CodeUnit>(units: &[U], s: &[u8]) -> bool {
+        units.len() == s.len() && units.iter().zip(s).all(|(u, b)| u.to_ascii() == Some(*b))
     }
 
-    /// Split path into segments based on path characteristics
+    /// Determine if path is rooted
     ///
     /// # Arguments
     ///
     /// * `path` - Path
-    pub fn split_path_segments<P: PathCharacteristics>(path: &str) -> Vec<&str> {
-        P::split_on_separators(path)
+    /// * `text` - Path as string, if known to be valid UTF-8
+    pub fn is_root<P: PathFlavor, U: CodeUnit>(path: &[U], text: Option<&str>) -> bool {
+        with_str(path, text, P::is_rooted)
     }
 
-    /// Join path segments to create a path based on path characteristics
+    /// Split path into prefix and remainder based on path characteristics
     ///
     /// # Arguments
     ///
-    /// * `segments` - Segments
-    pub fn join_path_segments<P: PathCharacteristics>(segments: &[&str]) -> String {
-        segments.join(&P::CANONICAL_SEPARATOR.to_string())
+    /// * `path` - Path
+    /// * `text` - Path as string, if known to be valid UTF-8
+    pub fn split_prefix<'a, P: PathFlavor, U: CodeUnit>(
+        path: &'a [U],
+        text: Option<&str>,
+    ) -> (Option<UnitPrefix<'a, U>>, &'a [U]) {
+        // Prefix returned by a custom flavour is only trusted if it really
+        // is a prefix of the path, since output is assumed to split the
+        // path at character boundaries
+        let prefix = with_str(path, text, |s| {
+            P::split_prefix(s)
+                .0
+                .filter(|prefix| s.starts_with(prefix.text))
+                .map(|prefix| (prefix.kind, prefix.text.len()))
+        });
+        match prefix {
+            Some((kind, len)) => {
+                let (text, rest) = path.split_at(len);
+                (Some(UnitPrefix { kind, text }), rest)
+            }
+            None => (None, path),
+        }
     }
 
-    /// Make an absolute path based on path characteristics
-    ///
-    /// # Arguments
-    ///
-    /// * `path` - Path
-    pub fn make_absolute<P: PathCharacteristics>(path: &str) -> String {
-        P::CANONICAL_SEPARATOR.to_string() + path
+    /// Output buffer that borrows from the input path for as long as the
+    /// output is identical to the start of the input and only allocates
+    /// once the two diverge
+    pub struct LazyBuf<'a, U> {
+        src: &'a [U],
+        buf: Option<Vec<U>>,
+        len: usize,
     }
 
-    #[cfg(test)]
-    mod tests {
-        use super::*;
+    impl<'a, U: CodeUnit> LazyBuf<'a, U> {
+        pub const fn new(src: &'a [U]) -> Self {
+            Self {
+                src,
+                buf: None,
+                len: 0,
+            }
+        }
 
-        #[test]
-        fn test_special_path_unix() {
-            assert_eq!(Some(String::from(".")), special_path::<UnixPath>(""));
-            assert_eq!(Some(String::from(".")), special_path::<UnixPath>("."));
-            assert_eq!(Some(String::from("..")), special_path::<UnixPath>(".."));
-            assert_eq!(Some(String::from("/")), special_path::<UnixPath>("/"));
-            assert_eq!(None, special_path::<UnixPath>("\\"));
-            assert_eq!(None, special_path::<UnixPath>("aaa"));
+        pub const fn len(&self) -> usize {
+            self.len
         }
 
-        #[test]
-        fn test_special_path_windows() {
-            assert_eq!(Some(String::from(".")), special_path::<WindowsPath>(""));
-            assert_eq!(Some(String::from(".")), special_path::<WindowsPath>("."));
-            assert_eq!(Some(String::from("..")), special_path::<WindowsPath>(".."));
-            assert_eq!(Some(String::from("\\")), special_path::<WindowsPath>("/"));
-            assert_eq!(Some(String::from("\\")), special_path::<WindowsPath>("\\"));
-            assert_eq!(None, special_path::<WindowsPath>("aaa"));
+        pub fn index(&self, i: usize) -> U {
+            self.buf.as_ref().map_or(self.src[i], |buf| buf[i])
         }
 
-        #[test]
-        fn test_is_root_unix() {
-            assert!(is_root::<UnixPath>("/a"));
-            assert!(!is_root::<UnixPath>("\\a"));
-            assert!(!is_root::<UnixPath>("a"));
+        pub fn truncate(&mut self, len: usize) {
+            self.len = self.len.min(len);
         }
 
-        #[test]
-        fn test_is_root_windows() {
-            assert!(is_root::<WindowsPath>("/a"));
-            assert!(is_root::<WindowsPath>("\\a"));
-            assert!(!is_root::<WindowsPath>("a"));
+        pub fn append(&mut self, unit: U) {
+            match &mut self.buf {
+                Some(buf) => {
+                    buf.truncate(self.len);
+                    buf.push(unit);
+                }
+                None if self.src.get(self.len) == Some(&unit) => {}
+                None => {
+                    let mut buf = Vec::with_capacity(self.src.len() + 1);
+                    buf.extend_from_slice(&self.src[..self.len]);
+                    buf.push(unit);
+                    self.buf = Some(buf);
+                }
+            }
+            self.len += 1;
         }
 
-        #[test]
-        fn test_trim_end_path_unix() {
-            assert_eq!("aaa", trim_end_path::<UnixPath>("aaa"));
-            assert_eq!("aaa", trim_end_path::<UnixPath>("aaa/"));
-            assert_eq!("aaa", trim_end_path::<UnixPath>("aaa/////"));
+        pub fn into_cow(self) -> Cow<'a, [U]> {
+            match self.buf {
+                Some(mut buf) => {
+                    buf.truncate(self.len);
+                    Cow::Owned(buf)
+                }
+                None => Cow::Borrowed(&self.src[..self.len]),
+            }
         }
+    }
 
-        #[test]
-        fn test_trim_end_path_windows() {
-            assert_eq!("aaa", trim_end_path::<WindowsPath>("aaa"));
-            assert_eq!("aaa", trim_end_path::<WindowsPath>("aaa/"));
-            assert_eq!("aaa", trim_end_path::<WindowsPath>("aaa\\"));
-            assert_eq!("aaa", trim_end_path::<WindowsPath>("aaa/////"));
-            assert_eq!("aaa", trim_end_path::<WindowsPath>("aaa\\\\\\\\\\"));
-            assert_eq!("aaa", trim_end_path::<WindowsPath>("aaa/\\/\\/"));
+    #[cfg(test)]
+    mod tests {
+        use super::LazyBuf;
+        use crate::flavor::{PathFlavor, PathPrefix, PrefixKind, UnixFlavor, WindowsFlavor};
+        use std::borrow::Cow;
+
+        fn to_str(units: &[u8]) -> &str {
+            std::str::from_utf8(units).unwrap()
         }
 
-        #[test]
-        fn test_split_path_segments_first_empty_unix() {
-            let segments = split_path_segments::<UnixPath>("/a/b/c");
-            assert_eq!(4, segments.len());
-            assert_eq!("", segments[0]);
-            assert_eq!("a", segments[1]);
-            assert_eq!("b", segments[2]);
-            assert_eq!("c", segments[3]);
+        fn is_root<P: PathFlavor>(path: &str) -> bool {
+            super::is_root::<P, u8>(path.as_bytes(), Some(path))
         }
 
-        #[test]
-        fn test_split_path_segments_last_empty_unix() {
-            let segments = split_path_segments::<UnixPath>("/a/b/c/");
-            assert_eq!(5, segments.len());
-            assert_eq!("", segments[0]);
-            assert_eq!("a", segments[1]);
-            assert_eq!("b", segments[2]);
-            assert_eq!("c", segments[3]);
-            assert_eq!("", segments[4]);
+        fn split_prefix<P: PathFlavor>(path: &str) -> (Option<PathPrefix<'_>>, &str) {
+            let (prefix, rest) = super::split_prefix::<P, u8>(path.as_bytes(), Some(path));
+            (
+                prefix.map(|prefix| PathPrefix {
+                    kind: prefix.kind,
+                    text: to_str(prefix.text),
+                }),
+                to_str(rest),
+            )
         }
 
         #[test]
-        fn test_split_path_segments_empty_unix() {
-            let segments = split_path_segments::<UnixPath>("");
-            assert_eq!(1, segments.len());
-            assert_eq!("", segments[0]);
+        fn test_lazy_buf_borrows_matching_output() {
+            let mut out = LazyBuf::new(b"a/b/c".as_slice());
+            for b in b"a/b" {
+                out.append(*b);
+            }
+            assert!(matches!(out.into_cow(), Cow::Borrowed(b"a/b")));
         }
 
         #[test]
-        fn test_split_path_segments_multiple_empty_unix() {
-            let segments = split_path_segments::<UnixPath>("//");
-            assert_eq!(3, segments.len());
-            assert_eq!("", segments[0]);
-            assert_eq!("", segments[1]);
-            assert_eq!("", segments[2]);
+        fn test_lazy_buf_allocates_on_divergence() {
+            let mut out = LazyBuf::new(b"a/b/c".as_slice());
+            for b in b"a/b" {
+                out.append(*b);
+            }
+            out.truncate(1);
+            out.append(b'\\');
+            out.append(b'x');
+            assert_eq!(out.index(1), b'\\');
+            assert!(matches!(out.into_cow(), Cow::Owned(v) if v == b"a\\x"));
         }
 
         #[test]
-        fn test_split_path_segments_first_empty_unix_backslashes() {
-            let segments = split_path_segments::<UnixPath>("/a\\b\\c");
-            assert_eq!(2, segments.len());
-            assert_eq!("", segments[0]);
-            assert_eq!("a\\b\\c", segments[1]);
+        fn test_lazy_buf_allocates_beyond_input() {
+            let mut out = LazyBuf::new(b"a".as_slice());
+            out.append(b'a');
+            out.append(b'/');
+            assert!(matches!(out.into_cow(), Cow::Owned(v) if v == b"a/"));
         }
 
         #[test]
-        fn test_split_path_segments_first_empty_windows_backslashes() {
-            let segments = split_path_segments::<WindowsPath>("\\a\\b\\c");
-            assert_eq!(4, segments.len());
-            assert_eq!("", segments[0]);
-            assert_eq!("a", segments[1]);
-            assert_eq!("b", segments[2]);
-            assert_eq!("c", segments[3]);
+        fn test_split_prefix_not_unicode() {
+            let path = b"\\\\sr\xffv\\sh\xfe\\aaa";
+            let (prefix, rest) = super::split_prefix::<WindowsFlavor, u8>(path, None);
+            let prefix = prefix.unwrap();
+            assert_eq!(PrefixKind::Root, prefix.kind);
+            assert_eq!(b"\\\\sr\xffv\\sh\xfe", prefix.text);
+            assert_eq!(b"\\aaa", rest);
         }
 
         #[test]
-        fn test_split_path_segments_first_empty_windows_mixture() {
-            let segments = split_path_segments::<WindowsPath>("/a\\b/c");
-            assert_eq!(4, segments.len());
-            assert_eq!("", segments[0]);
-            assert_eq!("a", segments[1]);
-            assert_eq!("b", segments[2]);
-            assert_eq!("c", segments[3]);
+        fn test_is_root_unix() {
+            assert!(is_root::<UnixFlavor>("/a"));
+            assert!(!is_root::<UnixFlavor>("\\a"));
+            assert!(!is_root::<UnixFlavor>("a"));
         }
 
         #[test]
-        fn test_join_path_segments_unix() {
-            assert_eq!("", join_path_segments::<UnixPath>(&[]));
-            assert_eq!("a/b/c", join_path_segments::<UnixPath>(&["a", "b", "c"]));
+        fn test_is_root_windows() {
+            assert!(is_root::<WindowsFlavor>("/a"));
+            assert!(is_root::<WindowsFlavor>("\\a"));
+            assert!(!is_root::<WindowsFlavor>("a"));
+        }
+
+        fn drive(text: &str) -> PathPrefix<'_> {
+            PathPrefix {
+                kind: PrefixKind::Drive,
+                text,
+            }
+        }
+
+        fn root(text: &str) -> PathPrefix<'_> {
+            PathPrefix {
+                kind: PrefixKind::Root,
+                text,
+            }
         }
 
         #[test]
-        fn test_join_path_segments_windows() {
-            assert_eq!("", join_path_segments::<WindowsPath>(&[]));
+        fn test_split_prefix_unix() {
+            assert_eq!((None, "C:/aaa"), split_prefix::<UnixFlavor>("C:/aaa"));
+            assert_eq!((None, "/aaa"), split_prefix::<UnixFlavor>("/aaa"));
             assert_eq!(
-                "a\\b\\c",
-                join_path_segments::<WindowsPath>(&["a", "b", "c"])
+                (None, "//srv/share"),
+                split_prefix::<UnixFlavor>("//srv/share")
             );
         }
 
         #[test]
-        fn test_make_absolute_unix() {
-            assert_eq!("/aaa", make_absolute::<UnixPath>("aaa"));
-            assert_eq!("//aaa", make_absolute::<UnixPath>("/aaa"));
-            assert_eq!("/\\aaa", make_absolute::<UnixPath>("\\aaa"));
+        fn test_split_prefix_windows() {
+            assert_eq!(
+                (Some(drive("C:")), "\\aaa"),
+                split_prefix::<WindowsFlavor>("C:\\aaa")
+            );
+            assert_eq!(
+                (Some(drive("c:")), "aaa"),
+                split_prefix::<WindowsFlavor>("c:aaa")
+            );
+            assert_eq!((Some(drive("Z:")), ""), split_prefix::<WindowsFlavor>("Z:"));
+            assert_eq!((None, "1:\\aaa"), split_prefix::<WindowsFlavor>("1:\\aaa"));
+            assert_eq!((None, "\\aaa"), split_prefix::<WindowsFlavor>("\\aaa"));
+            assert_eq!((None, "C"), split_prefix::<WindowsFlavor>("C"));
         }
 
         #[test]
-        fn test_make_absolute_windows() {
-            assert_eq!("\\aaa", make_absolute::<WindowsPath>("aaa"));
-            assert_eq!("\\/aaa", make_absolute::<WindowsPath>("/aaa"));
-            assert_eq!("\\\\aaa", make_absolute::<WindowsPath>("\\aaa"));
+        fn test_split_prefix_windows_unc() {
+            assert_eq!(
+                (Some(root("\\\\srv\\share")), "\\aaa"),
+                split_prefix::<WindowsFlavor>("\\\\srv\\share\\aaa")
+            );
+            assert_eq!(
+                (Some(root("//srv/share")), "/"),
+                split_prefix::<WindowsFlavor>("//srv/share/")
+            );
+            assert_eq!(
+                (Some(root("\\\\srv\\share")), ""),
+                split_prefix::<WindowsFlavor>("\\\\srv\\share")
+            );
+            assert_eq!(
+                (Some(root("\\\\srv")), ""),
+                split_prefix::<WindowsFlavor>("\\\\srv")
+            );
+            assert_eq!(
+                (Some(root("\\\\srv\\")), "\\\\aaa"),
+                split_prefix::<WindowsFlavor>("\\\\srv\\\\\\aaa")
+            );
+            assert_eq!((None, "\\\\"), split_prefix::<WindowsFlavor>("\\\\"));
+            assert_eq!(
+                (None, "\\\\\\aaa"),
+                split_prefix::<WindowsFlavor>("\\\\\\aaa")
+            );
+            assert_eq!((None, "\\\\.."), split_prefix::<WindowsFlavor>("\\\\.."));
         }
     }
 }
@@ -328,90 +442,360 @@ mod internal {
 /// If the result of this process is an empty string, return the string `"."`, representing the current directory.
 #[must_use]
 pub fn clean(path: &str) -> String {
-    #[cfg(not(target_os = "windows"))]
-    type PlatformPath = internal::UnixPath;
-    #[cfg(target_os = "windows")]
-    type PlatformPath = internal::WindowsPath;
-
-    clean_core::<PlatformPath>(path)
+    clean_with::<PlatformFlavor>(path)
 }
 
+/// Clean path according to Unix rules
 #[must_use]
 pub fn clean_unix(path: &str) -> String {
-    clean_core::<internal::UnixPath>(path)
+    clean_with::<UnixFlavor>(path)
 }
 
+/// Clean path according to Windows rules
 #[must_use]
 pub fn clean_windows(path: &str) -> String {
-    clean_core::<internal::WindowsPath>(path)
+    clean_with::<WindowsFlavor>(path)
 }
 
-#[allow(clippy::unnecessary_unwrap)]
-fn clean_core<P: PathCharacteristics>(path: &str) -> String {
-    use internal::{
-        is_root, join_path_segments, make_absolute, special_path, split_path_segments,
-        trim_end_path,
-    };
+/// Clean path according to rules of host operating system, borrowing
+/// from the input if it is already clean
+#[must_use]
+pub fn clean_cow(path: &str) -> Cow<'_, str> {
+    clean_with_cow::<PlatformFlavor>(path)
+}
+
+/// Clean path according to Unix rules, borrowing from the input if it is
+/// already clean
+#[must_use]
+pub fn clean_unix_cow(path: &str) -> Cow<'_, str> {
+    clean_with_cow::<UnixFlavor>(path)
+}
+
+/// Clean path according to Windows rules, borrowing from the input if it is
+/// already clean
+#[must_use]
+pub fn clean_windows_cow(path: &str) -> Cow<'_, str> {
+    clean_with_cow::<WindowsFlavor>(path)
+}
+
+/// Clean path according to rules of path flavour `P`
+#[must_use]
+pub fn clean_with<P: PathFlavor>(path: &str) -> String {
+    clean_with_cow::<P>(path).into_owned()
+}
+
+/// Clean path according to rules of path flavour `P`, borrowing from the
+/// input if it is already clean
+#[must_use]
+pub fn clean_with_cow<P: PathFlavor>(path: &str) -> Cow<'_, str> {
+    match clean_units::<P, u8>(path.as_bytes(), Some(path)) {
+        Cow::Borrowed(out) => Cow::Borrowed(&path[..out.len()]),
+        // SAFETY: cleaning splits UTF-8 input only at ASCII characters and
+        // inserts only ASCII characters, so output is valid UTF-8
+        Cow::Owned(out) => Cow::Owned(unsafe { String::from_utf8_unchecked(out) }),
+    }
+}
+
+/// Clean path according to rules of host operating system, preserving
+/// any parts of the path that are not valid Unicode
+#[must_use]
+pub fn clean_os(path: &OsStr) -> OsString {
+    clean_os_with::<PlatformFlavor>(path)
+}
+
+/// Clean path according to rules of path flavour `P`, preserving any parts
+/// of the path that are not valid Unicode
+#[must_use]
+pub fn clean_os_with<P: PathFlavor>(path: &OsStr) -> OsString {
+    let out = clean_units::<P, u8>(path.as_encoded_bytes(), None).into_owned();
+    // SAFETY: cleaning splits input only at ASCII characters and inserts
+    // only ASCII characters, which preserves validity of the encoding
+    unsafe { OsString::from_encoded_bytes_unchecked(out) }
+}
+
+/// Clean path encoded as arbitrary bytes (e.g. a Unix path) or as WTF-8
+/// (e.g. a Windows path) according to rules of path flavour `P`
+#[must_use]
+pub fn clean_bytes_with<P: PathFlavor>(path: &[u8]) -> Vec<u8> {
+    clean_units::<P, u8>(path, None).into_owned()
+}
+
+/// Clean path encoded as 16-bit units (e.g. a Windows wide string, which
+/// may contain unpaired surrogates) according to rules of path flavour `P`
+#[must_use]
+pub fn clean_wide_with<P: PathFlavor>(path: &[u16]) -> Vec<u16> {
+    clean_units::<P, u16>(path, None).into_owned()
+}
+
+/// Options controlling how [`clean_with_options`] cleans a path
+///
+/// The default options clean a path exactly as [`clean_with`] does.
+///
+/// ```rust
+/// use joat_path::{clean_unix_with_options, clean_windows_with_options, CleanOptions};
+///
+/// let opts = CleanOptions::new()
+///     .preserve_trailing_separator(true)
+///     .keep_leading_dot(true);
+/// assert_eq!(clean_unix_with_options("./aa//bb/../", &opts).unwrap(), "./aa/");
+///
+/// let opts = CleanOptions::new().separator('/');
+/// assert_eq!(clean_windows_with_options("C:\\aa\\bb", &opts).unwrap(), "C:/aa/bb");
+///
+/// let opts = CleanOptions::new().deny_parent_above_root(true);
+/// assert!(clean_unix_with_options("/aa/../..", &opts).is_err());
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CleanOptions {
+    preserve_trailing_separator: bool,
+    keep_leading_dot: bool,
+    deny_parent_above_root: bool,
+    separator: Option<char>,
+}
+
+impl CleanOptions {
+    /// Default options
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            preserve_trailing_separator: false,
+            keep_leading_dot: false,
+            deny_parent_above_root: false,
+            separator: None,
+        }
+    }
+
+    /// Keep trailing separator if path ends with one, e.g. `aa/bb/` cleans
+    /// to `aa/bb/` instead of `aa/bb`
+    ///
+    /// A path that cleans to a root, prefix or `.` is unaffected.
+    #[must_use]
+    pub const fn preserve_trailing_separator(mut self, value: bool) -> Self {
+        self.preserve_trailing_separator = value;
+        self
+    }
+
+    /// Keep leading `./` if path begins with one, e.g. `./aa/bb` cleans to
+    /// `./aa/bb` instead of `aa/bb`
+    #[must_use]
+    pub const fn keep_leading_dot(mut self, value: bool) -> Self {
+        self.keep_leading_dot = value;
+        self
+    }
+
+    /// Fail with [`crate::Error::EscapesRoot`] instead of discarding `..`
+    /// elements that would go above the root of a rooted path
+    #[must_use]
+    pub const fn deny_parent_above_root(mut self, value: bool) -> Self {
+        self.deny_parent_above_root = value;
+        self
+    }
+
+    /// Separator to write between path elements in place of the canonical
+    /// separator of the path flavour
+    ///
+    /// Cleaning fails with [`crate::Error::InvalidSeparator`] unless the
+    /// separator is an ASCII character recognized as a separator by the path
+    /// flavour, e.g. `/` for Windows.
+    #[must_use]
+    pub const fn separator(mut self, value: char) -> Self {
+        self.separator = Some(value);
+        self
+    }
+
+    fn separator_for<P: PathFlavor>(&self) -> crate::Result<u8> {
+        match self.separator {
+            None => Ok(P::CANONICAL_SEPARATOR as u8),
+            Some(c) if c.is_ascii() && P::is_separator(c) => Ok(c as u8),
+            Some(c) => Err(crate::Error::InvalidSeparator(c)),
+        }
+    }
+}
 
-    if let Some(s) = special_path::<P>(path) {
-        return s;
+/// Clean path according to Unix rules using the given options
+pub fn clean_unix_with_options(path: &str, options: &CleanOptions) -> crate::Result<String> {
+    clean_with_options::<UnixFlavor>(path, options)
+}
+
+/// Clean path according to Windows rules using the given options
+pub fn clean_windows_with_options(path: &str, options: &CleanOptions) -> crate::Result<String> {
+    clean_with_options::<WindowsFlavor>(path, options)
+}
+
+/// Clean path according to rules of path flavour `P` using the given
+/// options
+///
+/// # Arguments
+///
+/// * `path` - Path
+/// * `options` - Options
+pub fn clean_with_options<P: PathFlavor>(
+    path: &str,
+    options: &CleanOptions,
+) -> crate::Result<String> {
+    options.separator_for::<P>()?;
+    let (out, escapes_root) = clean_units_with::<P, u8>(path.as_bytes(), Some(path), options);
+    // SAFETY: cleaning splits UTF-8 input only at ASCII characters and
+    // inserts only ASCII characters, so output is valid UTF-8
+    let out = unsafe { String::from_utf8_unchecked(out.into_owned()) };
+    if escapes_root && options.deny_parent_above_root {
+        let (_, rest) = P::split_prefix(&out);
+        let root_len = out.len() - rest.len() + usize::from(P::is_rooted(rest));
+        return Err(crate::Error::EscapesRoot {
+            root: std::path::PathBuf::from(&out[..root_len]),
+            path: std::path::PathBuf::from(path),
+        });
     }
+    Ok(out)
+}
+
+fn clean_units<'a, P: PathFlavor, U: CodeUnit>(path: &'a [U], text: Option<&str>) -> Cow<'a, [U]> {
+    clean_units_with::<P, U>(path, text, &CleanOptions::new()).0
+}
+
+/// The core implementation: a single pass over the path which writes
+/// the cleaned path to a buffer that only allocates if the output
+/// diverges from the input, also returning true if a `..` element was
+/// discarded above the root
+fn clean_units_with<'a, P: PathFlavor, U: CodeUnit>(
+    path: &'a [U],
+    text: Option<&str>,
+    options: &CleanOptions,
+) -> (Cow<'a, [U]>, bool) {
+    use crate::flavor::PrefixKind;
+    use internal::{eq_ascii, is_root, is_separator, split_prefix, LazyBuf};
+
+    // Output is only valid UTF-8 or WTF-8 if inserted separators are ASCII
+    const { assert!(P::CANONICAL_SEPARATOR.is_ascii(), "separator must be ASCII") };
+
+    // Invalid separator is rejected by clean_with_options before this point
+    let separator = U::from_ascii(
+        options
+            .separator_for::<P>()
+            .unwrap_or(P::CANONICAL_SEPARATOR as u8),
+    );
+    let dot = U::from_ascii(b'.');
+
+    let (prefix, body) = split_prefix::<P, U>(path, text);
+    let body_text = text.map(|s| &s[path.len() - body.len()..]);
+    let mut out = LazyBuf::new(path);
+
+    let is_root = match prefix {
+        Some(prefix) => {
+            if prefix.kind == PrefixKind::Verbatim {
+                return (Cow::Borrowed(path), false);
+            }
 
-    let mut out = vec![];
-    let is_root = is_root::<P>(path);
+            for unit in prefix.text {
+                out.append(if is_separator::<P, U>(*unit) {
+                    separator
+                } else {
+                    *unit
+                });
+            }
+
+            prefix.kind == PrefixKind::Root || is_root::<P, U>(body, body_text)
+        }
+        None => is_root::<P, U>(body, body_text),
+    };
 
-    let path = trim_end_path::<P>(path);
-    let segments = split_path_segments::<P>(path);
-    let num_segments = segments.len();
+    let n = body.len();
+    let is_segment_end = |i: usize| i == n || is_separator::<P, U>(body[i]);
+    let mut r = 0;
+
+    let leading_dot = options.keep_leading_dot
+        && !is_root
+        && prefix.is_none()
+        && n > 1
+        && body[0] == dot
+        && is_separator::<P, U>(body[1]);
+
+    if is_root {
+        out.append(separator);
+    } else if leading_dot {
+        out.append(dot);
+        out.append(separator);
+        r = 2;
+    }
 
-    for segment in segments {
-        match segment {
-            "" => continue,
-            "." => {
-                if num_segments == 1 {
-                    out.push(segment);
-                };
-                continue;
+    // Start of first segment and of segments that can be removed by ".."
+    let start = out.len();
+    let mut backtrack = start;
+    let mut escapes_root = false;
+
+    while r < n {
+        if is_separator::<P, U>(body[r]) || (body[r] == dot && is_segment_end(r + 1)) {
+            // Empty or "." segment
+            r += 1;
+        } else if body[r] == dot && body.get(r + 1) == Some(&dot) && is_segment_end(r + 2) {
+            // ".." segment
+            r += 2;
+            if out.len() > backtrack {
+                out.truncate(out.len() - 1);
+                while out.len() > backtrack && !is_separator::<P, U>(out.index(out.len())) {
+                    out.truncate(out.len() - 1);
+                }
+            } else if is_root {
+                escapes_root = true;
+            } else {
+                if out.len() > start {
+                    out.append(separator);
+                }
+                out.append(dot);
+                out.append(dot);
+                backtrack = out.len();
             }
-            ".." => {
-                let previous = out.pop();
-                if previous.is_some() && !can_backtrack(previous.unwrap()) {
-                    out.push(previous.unwrap());
-                    out.push(segment);
-                } else if previous.is_none() && !is_root {
-                    out.push(segment);
-                };
-                continue;
+        } else {
+            if out.len() > start {
+                out.append(separator);
             }
-            _ => {
-                out.push(segment);
+            while r < n && !is_separator::<P, U>(body[r]) {
+                out.append(body[r]);
+                r += 1;
             }
-        };
+        }
     }
 
-    let out_str_0 = join_path_segments::<P>(&out);
+    if out.len() > start {
+        if options.preserve_trailing_separator && n > 0 && is_separator::<P, U>(body[n - 1]) {
+            out.append(separator);
+        }
+    } else if leading_dot {
+        // Only leading "./" remains
+        out.truncate(start - 1);
+    }
 
-    let out_str_1 = if is_root {
-        make_absolute::<P>(&out_str_0)
+    let out = if out.len() > 0 {
+        out.into_cow()
+    } else if eq_ascii(path, b".") {
+        Cow::Borrowed(path)
     } else {
-        out_str_0
+        Cow::Owned(vec![dot])
     };
 
-    if out_str_1.is_empty() {
-        ".".to_string()
-    } else {
-        out_str_1
+    // A relative path must not clean to a path with a prefix, e.g.
+    // `x\..\c:\windows` must not become `c:\windows`
+    if prefix.is_none() && !is_root && split_prefix::<P, U>(&out, None).0.is_some() {
+        let mut guarded = vec![dot, separator];
+        guarded.extend_from_slice(&out);
+        return (Cow::Owned(guarded), escapes_root);
     }
-}
 
-fn can_backtrack(segment: &str) -> bool {
-    !matches!(segment, "." | "..")
+    (out, escapes_root)
 }
 
 #[cfg(test)]
 mod tests {
     use super::test_helpers::to_windows;
-    use super::{clean_unix, clean_windows, PathClean};
+    use super::{
+        clean_bytes_with, clean_os, clean_os_with, clean_unix, clean_unix_cow,
+        clean_unix_with_options, clean_wide_with, clean_windows, clean_windows_cow,
+        clean_windows_with_options, clean_with, clean_with_options, CleanOptions, PathClean,
+    };
+    use crate::flavor::{PathFlavor, PathPrefix, PrefixKind};
+    use crate::flavor::{UnixFlavor, WindowsFlavor};
+    use proptest::prelude::*;
+    use std::borrow::Cow;
 
     use std::path::PathBuf;
 
@@ -498,6 +882,276 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_windows_drive_letter() {
+        let tests = vec![
+            ("C:", "C:"),
+            ("C:.", "C:"),
+            ("C:\\", "C:\\"),
+            ("C:/", "C:\\"),
+            ("C:\\..", "C:\\"),
+            ("C:\\..\\foo", "C:\\foo"),
+            ("C:\\foo\\..\\..\\bar", "C:\\bar"),
+            ("C:/foo//bar/", "C:\\foo\\bar"),
+            ("C:foo", "C:foo"),
+            ("C:foo\\..", "C:"),
+            ("C:..\\foo", "C:..\\foo"),
+            ("C:foo\\..\\..\\bar", "C:..\\bar"),
+            ("d:\\foo\\.\\bar", "d:\\foo\\bar"),
+            ("x\\..\\c:\\windows", ".\\c:\\windows"),
+            (".\\c:foo", ".\\c:foo"),
+            ("./a:.", ".\\a:."),
+            (".\\a:", ".\\a:"),
+            ("x/../c:", ".\\c:"),
+            ("x\\..\\ab:c", "ab:c"),
+        ];
+
+        for test in tests {
+            assert_eq!(clean_windows(test.0), test.1);
+            assert_eq!(clean_windows(test.1), test.1);
+        }
+    }
+
+    #[test]
+    fn test_windows_unc_share() {
+        let tests = vec![
+            ("\\\\srv\\share", "\\\\srv\\share\\"),
+            ("\\\\srv\\share\\", "\\\\srv\\share\\"),
+            ("\\\\srv\\share\\..", "\\\\srv\\share\\"),
+            ("\\\\srv\\share\\..\\x", "\\\\srv\\share\\x"),
+            ("\\\\srv\\share\\a\\..\\..\\b", "\\\\srv\\share\\b"),
+            ("\\\\srv\\share\\\\a\\.\\b\\", "\\\\srv\\share\\a\\b"),
+            ("//srv/share/a/b", "\\\\srv\\share\\a\\b"),
+            ("\\\\Srv\\Share$\\a", "\\\\Srv\\Share$\\a"),
+            ("\\\\srv", "\\\\srv\\"),
+            ("\\\\srv\\\\x\\..\\..\\y", "\\\\srv\\\\y"),
+            ("\\\\a\\\\bb", "\\\\a\\\\bb"),
+            ("//a//bb/", "\\\\a\\\\bb"),
+        ];
+
+        for test in tests {
+            assert_eq!(clean_windows(test.0), test.1);
+        }
+    }
+
+    #[test]
+    fn test_windows_device_ns() {
+        let tests = vec![
+            ("\\\\.\\PIPE\\x", "\\\\.\\PIPE\\x"),
+            ("\\\\.\\PIPE\\..\\x", "\\\\.\\PIPE\\x"),
+            ("\\\\.\\C:\\a\\..\\b", "\\\\.\\C:\\b"),
+            ("//./COM1", "\\\\.\\COM1\\"),
+            ("\\\\.\\", "\\"),
+            ("\\\\.\\\\x", "\\x"),
+        ];
+
+        for test in tests {
+            assert_eq!(clean_windows(test.0), test.1);
+        }
+    }
+
+    #[test]
+    fn test_windows_verbatim_unchanged() {
+        let tests = vec![
+            "\\\\?\\C:\\a\\..\\b",
+            "\\\\?\\C:\\a\\.\\b\\",
+            "\\\\?\\C:\\a/b",
+            "\\\\?\\UNC\\srv\\share\\a\\..\\b",
+            "\\\\?\\Volume{b75e2c83-0000-0000-0000-602f00000000}\\a\\..",
+        ];
+
+        for test in tests {
+            assert_eq!(clean_windows(test), test);
+        }
+    }
+
+    #[test]
+    fn test_unix_unc_share_is_rooted_path() {
+        assert_eq!(clean_unix("//srv/share/../x"), "/srv/x");
+    }
+
+    #[test]
+    fn test_unix_drive_letter_is_segment() {
+        assert_eq!(clean_unix("C:/../foo"), "foo");
+    }
+
+    #[test]
+    fn test_custom_flavor() {
+        struct S3KeyFlavor;
+
+        impl PathFlavor for S3KeyFlavor {
+            const CANONICAL_SEPARATOR: char = '/';
+
+            fn is_separator(c: char) -> bool {
+                c == '/'
+            }
+
+            fn split_prefix(path: &str) -> (Option<PathPrefix<'_>>, &str) {
+                match path.strip_prefix("s3://") {
+                    Some(rest) => {
+                        let end = rest.find('/').unwrap_or(rest.len()) + 5;
+                        let (text, rest) = path.split_at(end);
+                        (
+                            Some(PathPrefix {
+                                kind: PrefixKind::Root,
+                                text,
+                            }),
+                            rest,
+                        )
+                    }
+                    None => (None, path),
+                }
+            }
+        }
+
+        assert_eq!(clean_with::<S3KeyFlavor>("a//b/../c/"), "a/c");
+        assert_eq!(
+            clean_with::<S3KeyFlavor>("s3://bucket/a/../../b"),
+            "s3://bucket/b"
+        );
+        assert_eq!(clean_with::<S3KeyFlavor>("s3://bucket"), "s3://bucket/");
+    }
+
+    #[test]
+    fn test_custom_flavor_bogus_prefix_ignored() {
+        struct BogusFlavor;
+
+        impl PathFlavor for BogusFlavor {
+            const CANONICAL_SEPARATOR: char = '/';
+
+            fn is_separator(c: char) -> bool {
+                c == '/'
+            }
+
+            fn split_prefix(path: &str) -> (Option<PathPrefix<'_>>, &str) {
+                (
+                    Some(PathPrefix {
+                        kind: PrefixKind::Root,
+                        text: "\u{e9}",
+                    }),
+                    path,
+                )
+            }
+        }
+
+        assert_eq!(clean_with::<BogusFlavor>("a\u{e9}/../b"), "b");
+        assert_eq!(clean_with::<BogusFlavor>("/a/b"), "/a/b");
+        assert_eq!(
+            clean_os_with::<BogusFlavor>(std::ffi::OsStr::new("a/./b")),
+            "a/b"
+        );
+    }
+
+    #[test]
+    fn test_cow_borrows_clean_paths() {
+        let tests = vec![
+            ("/", "/"),
+            ("//", "/"),
+            (".", "."),
+            ("..", ".."),
+            ("a/b", "a/b"),
+            ("a/b/", "a/b"),
+            ("/a/b//", "/a/b"),
+            ("a/b/c/..", "a/b"),
+            ("../../a", "../../a"),
+        ];
+
+        for test in tests {
+            let out = clean_unix_cow(test.0);
+            assert!(matches!(out, Cow::Borrowed(_)), "{}", test.0);
+            assert_eq!(out, test.1);
+        }
+
+        let tests = vec![
+            ("C:\\a\\b", "C:\\a\\b"),
+            ("C:\\a\\b\\", "C:\\a\\b"),
+            ("\\\\srv\\share\\a", "\\\\srv\\share\\a"),
+            ("\\\\?\\C:\\a\\..", "\\\\?\\C:\\a\\.."),
+        ];
+
+        for test in tests {
+            let out = clean_windows_cow(test.0);
+            assert!(matches!(out, Cow::Borrowed(_)), "{}", test.0);
+            assert_eq!(out, test.1);
+        }
+    }
+
+    #[test]
+    fn test_cow_allocates_unclean_paths() {
+        let tests = vec![
+            ("", "."),
+            ("a/./b", "a/b"),
+            ("a/../b", "b"),
+            ("a//b", "a/b"),
+        ];
+
+        for test in tests {
+            let out = clean_unix_cow(test.0);
+            assert!(matches!(out, Cow::Owned(_)), "{}", test.0);
+            assert_eq!(out, test.1);
+        }
+
+        let tests = vec![("C:/a", "C:\\a"), ("\\\\srv\\share", "\\\\srv\\share\\")];
+
+        for test in tests {
+            let out = clean_windows_cow(test.0);
+            assert!(matches!(out, Cow::Owned(_)), "{}", test.0);
+            assert_eq!(out, test.1);
+        }
+    }
+
+    #[test]
+    fn test_not_unicode_bytes() {
+        let tests: Vec<(&[u8], &[u8])> = vec![
+            (b"a\xff/./b/../c\xfe//", b"a\xff/c\xfe"),
+            (b"/\xff/../..", b"/"),
+            (b"\xff\xfe", b"\xff\xfe"),
+        ];
+
+        for test in tests {
+            assert_eq!(clean_bytes_with::<UnixFlavor>(test.0), test.1);
+        }
+    }
+
+    #[test]
+    fn test_wtf8_bytes() {
+        // Unpaired surrogate U+D800 encoded as WTF-8
+        let tests: Vec<(&[u8], &[u8])> = vec![
+            (b"C:\\a\xed\xa0\x80/../b", b"C:\\b"),
+            (
+                b"\\\\s\xed\xa0\x80\\share\\..",
+                b"\\\\s\xed\xa0\x80\\share\\",
+            ),
+        ];
+
+        for test in tests {
+            assert_eq!(clean_bytes_with::<WindowsFlavor>(test.0), test.1);
+        }
+    }
+
+    #[test]
+    fn test_wide() {
+        let wide = |s: &str| s.encode_utf16().collect::<Vec<_>>();
+        let mut path = wide("C:/a/");
+        path.push(0xd800);
+        path.extend(wide("/./b/.."));
+        let mut expected = wide("C:\\a\\");
+        expected.push(0xd800);
+        assert_eq!(clean_wide_with::<WindowsFlavor>(&path), expected);
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn test_not_unicode_os() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+        use std::path::Path;
+
+        let path = Path::new(OsStr::from_bytes(b"/a\xff/../b\xfe/"));
+        assert_eq!(path.to_path_buf().clean().as_os_str().as_bytes(), b"/b\xfe");
+        assert_eq!(clean_os(path.as_os_str()).as_bytes(), b"/b\xfe");
+    }
+
     #[test]
     fn test_pathbuf_trait() {
         assert_eq!(
@@ -505,6 +1159,174 @@ mod tests {
             PathBuf::from("/path")
         );
     }
+
+    #[test]
+    fn test_trait_impls() {
+        use std::ffi::{OsStr, OsString};
+        use std::path::Path;
+
+        assert_eq!("a/./b/../c/".clean_unix(), "a/c");
+        assert_eq!(String::from("a/./b/../c/").clean_unix(), "a/c");
+        assert_eq!(OsStr::new("a/./b/../c/").clean_unix(), "a/c");
+        assert_eq!(OsString::from("a/./b/../c/").clean_unix(), "a/c");
+        assert_eq!(Path::new("a/./b/../c/").clean_unix(), Path::new("a/c"));
+        assert_eq!(
+            Cow::Borrowed(Path::new("a/./b/../c/")).clean_unix(),
+            Path::new("a/c")
+        );
+        assert_eq!(
+            Cow::<Path>::Owned(PathBuf::from("a/./b/../c/")).clean_unix(),
+            Path::new("a/c")
+        );
+    }
+
+    #[test]
+    fn test_options_default() {
+        let opts = CleanOptions::new();
+        for path in [
+            "",
+            ".",
+            "./aa/",
+            "/../aa//bb/",
+            "aa/../../bb",
+            "C:\\aa\\..\\..",
+        ] {
+            assert_eq!(
+                clean_unix_with_options(path, &opts).unwrap(),
+                clean_unix(path)
+            );
+            assert_eq!(
+                clean_windows_with_options(path, &opts).unwrap(),
+                clean_windows(path)
+            );
+        }
+    }
+
+    #[test]
+    fn test_options_preserve_trailing_separator() {
+        let opts = CleanOptions::new().preserve_trailing_separator(true);
+        let tests = vec![
+            ("aa/bb/", "aa/bb/"),
+            ("aa//bb//", "aa/bb/"),
+            ("aa/bb/./", "aa/bb/"),
+            ("aa/bb/../", "aa/"),
+            ("aa/bb", "aa/bb"),
+            ("aa/..", "."),
+            ("aa/../", "."),
+            ("/", "/"),
+            ("/aa/../", "/"),
+        ];
+        for test in tests {
+            assert_eq!(clean_unix_with_options(test.0, &opts).unwrap(), test.1);
+        }
+        assert_eq!(
+            clean_windows_with_options("C:aa/", &opts).unwrap(),
+            "C:aa\\"
+        );
+        assert_eq!(clean_windows_with_options("C:aa/../", &opts).unwrap(), "C:");
+    }
+
+    #[test]
+    fn test_options_keep_leading_dot() {
+        let opts = CleanOptions::new().keep_leading_dot(true);
+        let tests = vec![
+            ("./aa/bb", "./aa/bb"),
+            (".//aa/./bb", "./aa/bb"),
+            ("./../aa", "./../aa"),
+            ("./aa/..", "."),
+            ("./", "."),
+            ("aa/./bb", "aa/bb"),
+            ("/./aa", "/aa"),
+        ];
+        for test in tests {
+            assert_eq!(clean_unix_with_options(test.0, &opts).unwrap(), test.1);
+        }
+        assert_eq!(clean_windows_with_options(".\\aa", &opts).unwrap(), ".\\aa");
+        assert_eq!(clean_windows_with_options("C:./aa", &opts).unwrap(), "C:aa");
+    }
+
+    #[test]
+    fn test_options_deny_parent_above_root() {
+        use crate::error::Error;
+
+        let opts = CleanOptions::new().deny_parent_above_root(true);
+        assert_eq!(clean_unix_with_options("/aa/../bb", &opts).unwrap(), "/bb");
+        assert_eq!(clean_unix_with_options("../aa", &opts).unwrap(), "../aa");
+        match clean_unix_with_options("/aa/../../bb", &opts) {
+            Err(Error::EscapesRoot { root, path }) => {
+                assert_eq!(root, PathBuf::from("/"));
+                assert_eq!(path, PathBuf::from("/aa/../../bb"));
+            }
+            result => panic!("unexpected result {result:?}"),
+        }
+        match clean_windows_with_options("\\\\srv\\share\\..", &opts) {
+            Err(Error::EscapesRoot { root, .. }) => {
+                assert_eq!(root, PathBuf::from("\\\\srv\\share\\"));
+            }
+            result => panic!("unexpected result {result:?}"),
+        }
+        assert!(clean_windows_with_options("C:\\..", &opts).is_err());
+        assert!(clean_windows_with_options("C:..", &opts).is_ok());
+    }
+
+    #[test]
+    fn test_options_separator() {
+        use crate::error::Error;
+
+        let opts = CleanOptions::new().separator('/');
+        assert_eq!(
+            clean_windows_with_options("\\\\srv\\share\\aa\\..\\bb", &opts).unwrap(),
+            "//srv/share/bb"
+        );
+        assert_eq!(
+            clean_windows_with_options("C:\\aa", &opts).unwrap(),
+            "C:/aa"
+        );
+        assert_eq!(
+            clean_windows_with_options("\\\\?\\C:\\aa\\..", &opts).unwrap(),
+            "\\\\?\\C:\\aa\\.."
+        );
+
+        let opts = CleanOptions::new().separator('\\');
+        assert!(matches!(
+            clean_unix_with_options("aa\\bb/cc", &opts),
+            Err(Error::InvalidSeparator('\\'))
+        ));
+        assert_eq!(
+            clean_with_options::<WindowsFlavor>("aa/bb", &opts).unwrap(),
+            "aa\\bb"
+        );
+
+        for separator in ['|', '\u{2215}'] {
+            let opts = CleanOptions::new().separator(separator);
+            assert!(matches!(
+                clean_windows_with_options("aa/bb", &opts),
+                Err(Error::InvalidSeparator(c)) if c == separator
+            ));
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn prop_clean_windows_idempotent(path in "[aC:.?/\\\\]{0,12}") {
+            let once = clean_windows(&path);
+            prop_assert_eq!(clean_windows(&once), once);
+        }
+
+        #[test]
+        fn prop_clean_unix_idempotent(path in "[aC:./\\\\]{0,12}") {
+            let once = clean_unix(&path);
+            prop_assert_eq!(clean_unix(&once), once);
+        }
+    }
+
+    #[test]
+    fn test_trait_flavors() {
+        assert_eq!("C:/a/../b".clean_windows(), "C:\\b");
+        assert_eq!("C:/a/../b".clean_unix(), "C:/b");
+        assert_eq!("/a//b".clean_with::<WindowsFlavor>(), "\\a\\b");
+        assert_eq!("a/../b".clean(), "b");
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,306 @@
+// Copyright (c) 2020-3 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+use crate::error::{Error, Result};
+use crate::flavor::{PathFlavor, PlatformFlavor, UnixFlavor, WindowsFlavor};
+use crate::path_clean::clean_with;
+use std::path::{Path, PathBuf};
+
+/// Compute relative path from one directory to a target path without
+/// accessing the file system, according to rules of host operating system
+///
+/// # Arguments
+///
+/// * `from_dir` - Directory from which resulting path is relative
+/// * `path` - Target path
+pub fn relative_path<B: AsRef<Path>, P: AsRef<Path>>(from_dir: B, path: P) -> Result<PathBuf> {
+    fn to_str(path: &Path) -> Result<&str> {
+        path.to_str()
+            .ok_or_else(|| Error::NotUnicode(path.to_path_buf()))
+    }
+
+    relative_path_with::<PlatformFlavor>(to_str(from_dir.as_ref())?, to_str(path.as_ref())?)
+        .map(PathBuf::from)
+}
+
+/// Compute relative path according to Unix rules
+///
+/// ```rust
+/// use joat_path::relative_path_unix;
+/// assert_eq!(relative_path_unix("/aa/bb/cc", "/aa/dd").unwrap(), "../../dd");
+/// ```
+pub fn relative_path_unix(from_dir: &str, path: &str) -> Result<String> {
+    relative_path_with::<UnixFlavor>(from_dir, path)
+}
+
+/// Compute relative path according to Windows rules
+///
+/// Fails if paths are on different drives or UNC shares.
+///
+/// ```rust
+/// use joat_path::relative_path_windows;
+/// assert_eq!(relative_path_windows("C:\\aa\\bb", "c:/aa/cc").unwrap(), "..\\cc");
+/// assert!(relative_path_windows("C:\\aa", "D:\\aa").is_err());
+/// ```
+pub fn relative_path_windows(from_dir: &str, path: &str) -> Result<String> {
+    relative_path_with::<WindowsFlavor>(from_dir, path)
+}
+
+/// Compute relative path according to rules of path flavour `P`
+///
+/// Both paths are cleaned before comparison. Fails if the paths have
+/// different prefixes, if only one of the paths is rooted or if the
+/// relative path cannot be determined lexically, e.g. from `../aa` to `bb`.
+///
+/// # Arguments
+///
+/// * `from_dir` - Directory from which resulting path is relative
+/// * `path` - Target path
+pub fn relative_path_with<P: PathFlavor>(from_dir: &str, path: &str) -> Result<String> {
+    let from_dir_clean = clean_with::<P>(from_dir);
+    let path_clean = clean_with::<P>(path);
+
+    let (from_dir_prefix, from_dir_rest) = P::split_prefix(&from_dir_clean);
+    let (path_prefix, path_rest) = P::split_prefix(&path_clean);
+
+    let same_prefix = match (from_dir_prefix, path_prefix) {
+        (None, None) => true,
+        (Some(a), Some(b)) => a.kind == b.kind && is_same_prefix::<P>(a.text, b.text),
+        _ => false,
+    };
+    if !same_prefix || P::is_rooted(from_dir_rest) != P::is_rooted(path_rest) {
+        return Err(Error::PrefixMismatch {
+            from: PathBuf::from(from_dir),
+            to: PathBuf::from(path),
+        });
+    }
+
+    let from_dir_segments = segments::<P>(from_dir_rest);
+    let path_segments = segments::<P>(path_rest);
+
+    let common = from_dir_segments
+        .iter()
+        .zip(&path_segments)
+        .take_while(|(a, b)| P::is_same_segment(a, b))
+        .count();
+
+    if from_dir_segments[common..].contains(&"..") {
+        return Err(Error::Indeterminate {
+            from: PathBuf::from(from_dir),
+            to: PathBuf::from(path),
+        });
+    }
+
+    let out = std::iter::repeat_n("..", from_dir_segments.len() - common)
+        .chain(path_segments[common..].iter().copied())
+        .collect::<Vec<_>>();
+
+    if out.is_empty() {
+        return Ok(String::from("."));
+    }
+
+    // Relative path must not parse as a path with a prefix, e.g. `c:foo`
+    let separator = P::CANONICAL_SEPARATOR;
+    let out = out.join(&separator.to_string());
+    if P::split_prefix(&out).0.is_some() {
+        Ok(format!(".{separator}{out}"))
+    } else {
+        Ok(out)
+    }
+}
+
+fn is_same_prefix<P: PathFlavor>(a: &str, b: &str) -> bool {
+    let mut a = a.split(P::is_separator);
+    let mut b = b.split(P::is_separator);
+    loop {
+        match (a.next(), b.next()) {
+            (Some(a), Some(b)) if P::is_same_segment(a, b) => {}
+            (None, None) => return true,
+            _ => return false,
+        }
+    }
+}
+
+fn segments<P: PathFlavor>(path: &str) -> Vec<&str> {
+    path.split(P::is_separator)
+        .filter(|s| !s.is_empty() && *s != ".")
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{relative_path, relative_path_unix, relative_path_windows};
+    use crate::error::Error;
+    use crate::test_helpers::abs;
+    use std::path::Path;
+
+    #[test]
+    fn test_unix() {
+        let tests = vec![
+            ("/aa/bb/cc", "/aa/dd", "../../dd"),
+            ("/aa", "/aa", "."),
+            ("/aa/", "/aa/./bb/", "bb"),
+            ("/aa", "/AA/bb", "../AA/bb"),
+            ("/", "/aa/bb", "aa/bb"),
+            ("/aa/bb", "/", "../.."),
+            ("/aa/../bb", "/aa", "../aa"),
+            ("aa/bb", "aa/bb/cc", "cc"),
+            ("aa", "../bb", "../../bb"),
+            ("../aa", "../bb", "../bb"),
+            ("", "aa", "aa"),
+        ];
+
+        for test in tests {
+            assert_eq!(relative_path_unix(test.0, test.1).unwrap(), test.2);
+        }
+    }
+
+    #[test]
+    fn test_unix_fails() {
+        assert!(relative_path_unix("/aa", "bb").is_err());
+        assert!(relative_path_unix("aa", "/bb").is_err());
+        assert!(matches!(
+            relative_path_unix("../aa", "bb"),
+            Err(Error::Indeterminate { .. })
+        ));
+    }
+
+    #[test]
+    fn test_windows() {
+        let tests = vec![
+            ("C:\\aa\\bb", "C:\\aa\\cc", "..\\cc"),
+            ("C:/aa/bb", "c:\\aa\\bb\\cc", "cc"),
+            ("C:\\", "C:\\aa", "aa"),
+            ("C:aa", "C:bb", "..\\bb"),
+            ("\\\\srv\\share\\aa", "\\\\SRV\\share\\bb", "..\\bb"),
+            ("\\aa", "\\bb", "..\\bb"),
+            ("C:\\a", "C:\\A\\b", "b"),
+            ("C:\\Aa\\Bb", "c:\\aA\\cc", "..\\cc"),
+            ("C:\\\u{3a3}\u{3c2}", "C:\\\u{3c3}\u{3a3}\\x", "x"),
+        ];
+
+        for test in tests {
+            assert_eq!(relative_path_windows(test.0, test.1).unwrap(), test.2);
+        }
+    }
+
+    #[test]
+    fn test_windows_fails() {
+        assert!(matches!(
+            relative_path_windows("C:\\aa", "D:\\aa"),
+            Err(Error::PrefixMismatch { .. })
+        ));
+        assert!(relative_path_windows("C:\\aa", "C:aa").is_err());
+        assert!(relative_path_windows("C:\\aa", "\\aa").is_err());
+        assert!(relative_path_windows("\\\\srv\\s1\\aa", "\\\\srv\\s2\\aa").is_err());
+        assert!(relative_path_windows("\\\\srv\\share\\aa", "C:\\aa").is_err());
+    }
+
+    #[test]
+    fn test_windows_drive_like_segment() {
+        use crate::absolute_path_windows;
+
+        let tests = vec![
+            (".", "x\\..\\c:foo", ".\\c:foo"),
+            ("a", "a\\.\\c:foo", ".\\c:foo"),
+            ("a", "a\\c:foo\\b", ".\\c:foo\\b"),
+            ("a", "a\\b\\c:foo", "b\\c:foo"),
+            ("C:\\w\\a", "C:\\w\\.\\c:foo", "..\\c:foo"),
+        ];
+
+        for test in tests {
+            let relative = relative_path_windows(test.0, test.1).unwrap();
+            assert_eq!(relative, test.2);
+
+            let base = absolute_path_windows("C:\\w", test.0).unwrap();
+            assert_eq!(
+                absolute_path_windows(&base, &relative).unwrap(),
+                absolute_path_windows("C:\\w", test.1).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_windows_prefix_case() {
+        assert_eq!(
+            relative_path_windows("\\\\\u{dc}nc\\share\\a", "\\\\\u{fc}nc\\SHARE\\b").unwrap(),
+            "..\\b"
+        );
+        assert_eq!(
+            relative_path_windows("C:\\\u{c4}", "c:\\\u{e4}\\b").unwrap(),
+            "b"
+        );
+    }
+
+    #[test]
+    fn test_case_sensitive_flavor() {
+        use crate::flavor::{PathFlavor, PathPrefix, PrefixKind};
+        use crate::relative_path_with;
+
+        struct S3KeyFlavor;
+
+        impl PathFlavor for S3KeyFlavor {
+            const CANONICAL_SEPARATOR: char = '/';
+
+            fn is_separator(c: char) -> bool {
+                c == '/'
+            }
+
+            fn split_prefix(path: &str) -> (Option<PathPrefix<'_>>, &str) {
+                match path.strip_prefix("s3://") {
+                    Some(rest) => {
+                        let end = rest.find('/').unwrap_or(rest.len()) + 5;
+                        let (text, rest) = path.split_at(end);
+                        (
+                            Some(PathPrefix {
+                                kind: PrefixKind::Root,
+                                text,
+                            }),
+                            rest,
+                        )
+                    }
+                    None => (None, path),
+                }
+            }
+        }
+
+        assert_eq!(
+            relative_path_with::<S3KeyFlavor>("s3://bucket/a", "s3://bucket/b").unwrap(),
+            "../b"
+        );
+        assert!(matches!(
+            relative_path_with::<S3KeyFlavor>("s3://Bucket/a", "s3://bucket/b"),
+            Err(Error::PrefixMismatch { .. })
+        ));
+        assert_eq!(
+            relative_path_with::<S3KeyFlavor>("s3://bucket/A", "s3://bucket/a").unwrap(),
+            "../a"
+        );
+    }
+
+    #[test]
+    fn test_host() {
+        assert_eq!(
+            relative_path(abs("/aa/bb"), abs("/aa/cc")).unwrap(),
+            Path::new("..").join("cc")
+        );
+    }
+}
@@ -0,0 +1,269 @@
+// Copyright (c) 2020-3 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+use crate::absolute_path::absolute_path;
+use crate::absolute_path_buf::AbsolutePathBuf;
+use crate::error::{Error, Result};
+use crate::path_clean::clean_os;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::path::{Component, Path, PathBuf};
+
+/// Owned path that is guaranteed to be relative and cleaned
+///
+/// A path created with [`RelativePathBuf::new_contained`] is additionally
+/// guaranteed never to begin with `..` and so can never escape the
+/// directory it is resolved against. This guarantee is preserved by
+/// [`RelativePathBuf::join`] and [`RelativePathBuf::push`].
+///
+/// ```rust
+/// use joat_path::RelativePathBuf;
+/// use std::path::Path;
+///
+/// # #[cfg(not(target_os = "windows"))]
+/// # {
+/// let p = RelativePathBuf::new("aa/./bb/../cc").unwrap();
+/// assert_eq!(p.as_path(), Path::new("aa/cc"));
+/// assert_eq!(p.to_absolute("/base").unwrap().as_path(), Path::new("/base/aa/cc"));
+///
+/// assert!(RelativePathBuf::new("/aa").is_err());
+/// assert!(RelativePathBuf::new_contained("aa/../../bb").is_err());
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct RelativePathBuf {
+    path: PathBuf,
+    contained: bool,
+}
+
+impl RelativePathBuf {
+    /// Clean relative path, failing if path is rooted
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path
+    pub fn new<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::with_containment(path.as_ref(), false)
+    }
+
+    /// Clean relative path, failing if path is rooted or if cleaned path
+    /// begins with `..`
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path
+    pub fn new_contained<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::with_containment(path.as_ref(), true)
+    }
+
+    fn with_containment(path: &Path, contained: bool) -> Result<Self> {
+        if matches!(
+            path.components().next(),
+            Some(Component::Prefix(_) | Component::RootDir)
+        ) {
+            return Err(Error::NotRelative(path.to_path_buf()));
+        }
+
+        let path = PathBuf::from(clean_os(path.as_os_str()));
+
+        if contained && path.starts_with(Component::ParentDir) {
+            return Err(Error::EscapesRoot {
+                root: PathBuf::from("."),
+                path,
+            });
+        }
+
+        Ok(Self { path, contained })
+    }
+
+    /// Borrow as plain path
+    #[must_use]
+    pub fn as_path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Convert into plain path
+    #[must_use]
+    pub fn into_path_buf(self) -> PathBuf {
+        self.path
+    }
+
+    /// Returns true if path is guaranteed not to begin with `..`, false
+    /// otherwise
+    #[must_use]
+    pub const fn is_contained(&self) -> bool {
+        self.contained
+    }
+
+    /// Join relative path onto this path, producing a cleaned relative path
+    /// subject to the same containment rule as this path
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path (must be relative)
+    pub fn join<P: AsRef<Path>>(&self, path: P) -> Result<Self> {
+        Self::with_containment(&self.path.join(path), self.contained)
+    }
+
+    /// Extend this path with relative path, subject to the same containment
+    /// rule as this path
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path (must be relative)
+    pub fn push<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        *self = self.join(path)?;
+        Ok(())
+    }
+
+    /// Resolve this path against a base directory
+    ///
+    /// # Arguments
+    ///
+    /// * `base_dir` - Base directory (must be absolute)
+    pub fn to_absolute<B: AsRef<Path>>(&self, base_dir: B) -> Result<AbsolutePathBuf> {
+        absolute_path(base_dir, &self.path)
+    }
+}
+
+impl Deref for RelativePathBuf {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl AsRef<Path> for RelativePathBuf {
+    fn as_ref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl PartialEq for RelativePathBuf {
+    fn eq(&self, other: &Self) -> bool {
+        self.path == other.path
+    }
+}
+
+impl Eq for RelativePathBuf {}
+
+impl Hash for RelativePathBuf {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.path.hash(state);
+    }
+}
+
+/// Cleans path, failing if path is rooted
+impl TryFrom<PathBuf> for RelativePathBuf {
+    type Error = Error;
+
+    fn try_from(value: PathBuf) -> Result<Self> {
+        Self::new(value)
+    }
+}
+
+impl From<RelativePathBuf> for PathBuf {
+    fn from(value: RelativePathBuf) -> Self {
+        value.path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RelativePathBuf;
+    use crate::error::Error;
+    use crate::test_helpers::abs;
+    use std::path::Path;
+
+    #[test]
+    fn test_new() {
+        assert_eq!(
+            RelativePathBuf::new("aa/./bb/../cc/").unwrap().as_path(),
+            Path::new("aa").join("cc")
+        );
+        assert_eq!(RelativePathBuf::new("").unwrap().as_path(), Path::new("."));
+        assert_eq!(
+            RelativePathBuf::new("../aa").unwrap().as_path(),
+            Path::new("..").join("aa")
+        );
+    }
+
+    #[test]
+    fn test_new_rooted_fails() {
+        assert!(matches!(
+            RelativePathBuf::new(abs("/aa")),
+            Err(Error::NotRelative(_))
+        ));
+        assert!(RelativePathBuf::new_contained(abs("/aa")).is_err());
+        #[cfg(target_os = "windows")]
+        {
+            assert!(RelativePathBuf::new("C:aa").is_err());
+            assert!(RelativePathBuf::new("C:\\aa").is_err());
+            assert!(RelativePathBuf::new("\\aa").is_err());
+        }
+    }
+
+    #[test]
+    fn test_new_contained() {
+        assert_eq!(
+            RelativePathBuf::new_contained("aa/../bb")
+                .unwrap()
+                .as_path(),
+            Path::new("bb")
+        );
+        assert!(matches!(
+            RelativePathBuf::new_contained("aa/../.."),
+            Err(Error::EscapesRoot { .. })
+        ));
+        assert!(RelativePathBuf::new_contained("../aa").is_err());
+    }
+
+    #[test]
+    fn test_join() {
+        let p = RelativePathBuf::new_contained("aa/bb").unwrap();
+        assert_eq!(
+            p.join("../cc").unwrap().as_path(),
+            Path::new("aa").join("cc")
+        );
+        assert!(p.join("../../..").is_err());
+        assert!(p.join(abs("/cc")).is_err());
+
+        let p = RelativePathBuf::new("aa").unwrap();
+        assert_eq!(p.join("../..").unwrap().as_path(), Path::new(".."));
+    }
+
+    #[test]
+    fn test_push() {
+        let mut p = RelativePathBuf::new_contained("aa").unwrap();
+        p.push("bb").unwrap();
+        assert_eq!(p.as_path(), Path::new("aa").join("bb"));
+        assert!(p.push("../../..").is_err());
+        assert_eq!(p.as_path(), Path::new("aa").join("bb"));
+    }
+
+    #[test]
+    fn test_to_absolute() {
+        let p = RelativePathBuf::new("../aa").unwrap();
+        assert_eq!(p.to_absolute(abs("/base")).unwrap().as_path(), abs("/aa"));
+        assert!(p.to_absolute("base").is_err());
+    }
+}
@@ -0,0 +1,262 @@
+// Copyright (c) 2020-3 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+use crate::absolute_path::{absolute_path_unchecked, absolute_path_with};
+use crate::absolute_path_buf::AbsolutePathBuf;
+use crate::error::{Error, Result};
+use crate::flavor::{PathFlavor, PrefixKind, UnixFlavor, WindowsFlavor};
+use crate::path_clean::{clean_os, clean_with};
+use std::path::{Component, Path, PathBuf};
+
+/// Join untrusted relative path onto root directory according to rules of
+/// host operating system, failing if the result would lie outside the root
+/// directory
+///
+/// Absolute paths, paths with a prefix such as a drive letter or UNC share
+/// and paths with `..` elements that climb above the root directory, even
+/// if they later descend back into it, are all rejected.
+///
+/// # Arguments
+///
+/// * `root` - Root directory (must be absolute)
+/// * `path` - Untrusted path
+pub fn safe_join<R: AsRef<Path>, P: AsRef<Path>>(root: R, path: P) -> Result<AbsolutePathBuf> {
+    let root = root.as_ref();
+    let path = path.as_ref();
+
+    let escapes_root = || Error::EscapesRoot {
+        root: root.to_path_buf(),
+        path: path.to_path_buf(),
+    };
+
+    if !root.is_absolute() {
+        return Err(Error::BaseNotAbsolute(root.to_path_buf()));
+    }
+
+    if matches!(
+        path.components().next(),
+        Some(Component::Prefix(_) | Component::RootDir)
+    ) {
+        return Err(escapes_root());
+    }
+
+    // Reject any ".." that climbs above the root, even if the path later
+    // descends back into it
+    let path_clean = PathBuf::from(clean_os(path.as_os_str()));
+    if path_clean.components().next() == Some(Component::ParentDir) {
+        return Err(escapes_root());
+    }
+
+    Ok(absolute_path_unchecked(root, &path_clean))
+}
+
+/// Join untrusted relative path onto root directory according to Unix rules
+///
+/// ```rust
+/// use joat_path::safe_join_unix;
+/// assert_eq!(safe_join_unix("/srv/www", "a/../b").unwrap(), "/srv/www/b");
+/// assert!(safe_join_unix("/srv/www", "a/../../etc/passwd").is_err());
+/// ```
+pub fn safe_join_unix(root: &str, path: &str) -> Result<String> {
+    safe_join_with::<UnixFlavor>(root, path)
+}
+
+/// Join untrusted relative path onto root directory according to Windows rules
+///
+/// ```rust
+/// use joat_path::safe_join_windows;
+/// assert_eq!(safe_join_windows("C:\\www", "a/b").unwrap(), "C:\\www\\a\\b");
+/// assert!(safe_join_windows("C:\\www", "D:\\evil").is_err());
+/// assert!(safe_join_windows("C:\\www", "\\\\srv\\share\\evil").is_err());
+/// ```
+pub fn safe_join_windows(root: &str, path: &str) -> Result<String> {
+    safe_join_with::<WindowsFlavor>(root, path)
+}
+
+/// Join untrusted relative path onto root directory according to rules of
+/// path flavour `P`
+///
+/// # Arguments
+///
+/// * `root` - Root directory (must be rooted)
+/// * `path` - Untrusted path
+pub fn safe_join_with<P: PathFlavor>(root: &str, path: &str) -> Result<String> {
+    let root_clean = clean_with::<P>(root);
+    let (root_prefix, root_rest) = P::split_prefix(&root_clean);
+    let is_root_verbatim = root_prefix.is_some_and(|prefix| prefix.kind == PrefixKind::Verbatim);
+    let is_root_rooted = match root_prefix {
+        Some(prefix) if prefix.kind != PrefixKind::Drive => true,
+        _ => P::is_rooted(root_rest),
+    };
+    if !is_root_rooted {
+        return Err(Error::BaseNotAbsolute(PathBuf::from(root)));
+    }
+
+    let escapes_root = || Error::EscapesRoot {
+        root: PathBuf::from(root),
+        path: PathBuf::from(path),
+    };
+
+    if P::split_prefix(path).0.is_some() || P::is_rooted(path) {
+        return Err(escapes_root());
+    }
+
+    let path_clean = clean_with::<P>(path);
+    if path_clean.split(P::is_separator).next() == Some("..") {
+        return Err(escapes_root());
+    }
+
+    if is_root_verbatim {
+        // Verbatim root is not normalized, so only path segments are joined
+        absolute_path_with::<P>(&root_clean, &path_clean)
+    } else if path_clean == "." {
+        Ok(root_clean)
+    } else if root_clean.ends_with(P::is_separator) {
+        Ok(root_clean + &path_clean)
+    } else {
+        Ok(root_clean + &P::CANONICAL_SEPARATOR.to_string() + &path_clean)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{safe_join, safe_join_unix, safe_join_windows};
+    use crate::error::Error;
+    use crate::test_helpers::abs;
+
+    #[test]
+    fn test_unix() {
+        let tests = vec![
+            ("/srv", "a/b", "/srv/a/b"),
+            ("/srv/./www/", "a/../b", "/srv/www/b"),
+            ("/srv", "", "/srv"),
+            ("/srv", "a/..", "/srv"),
+            ("/", "a", "/a"),
+            ("/srv", "..\\..\\a", "/srv/..\\..\\a"),
+        ];
+
+        for test in tests {
+            assert_eq!(safe_join_unix(test.0, test.1).unwrap(), test.2);
+        }
+    }
+
+    #[test]
+    fn test_unix_escapes_root() {
+        for path in [
+            "..",
+            "../srv2",
+            "../srv/x",
+            "a/../../b",
+            "/etc/passwd",
+            "a/../../../..",
+        ] {
+            assert!(matches!(
+                safe_join_unix("/srv", path),
+                Err(Error::EscapesRoot { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_unix_root_not_absolute() {
+        assert!(matches!(
+            safe_join_unix("srv", "a"),
+            Err(Error::BaseNotAbsolute(_))
+        ));
+    }
+
+    #[test]
+    fn test_windows() {
+        let tests = vec![
+            ("C:\\www", "a/b", "C:\\www\\a\\b"),
+            ("C:\\", "a\\..\\b", "C:\\b"),
+            ("\\\\srv\\share", "a", "\\\\srv\\share\\a"),
+            (
+                "\\\\srv\\share\\www",
+                "a\\.\\b\\",
+                "\\\\srv\\share\\www\\a\\b",
+            ),
+            ("\\\\?\\C:\\www", "a/./b", "\\\\?\\C:\\www\\a\\b"),
+            ("\\\\?\\C:\\www\\", ".", "\\\\?\\C:\\www\\"),
+        ];
+
+        for test in tests {
+            assert_eq!(safe_join_windows(test.0, test.1).unwrap(), test.2);
+        }
+    }
+
+    #[test]
+    fn test_windows_escapes_root() {
+        for path in [
+            "..\\x",
+            "a/../../x",
+            "\\x",
+            "/x",
+            "C:\\x",
+            "C:x",
+            "\\\\srv\\share\\x",
+            "//srv/share/x",
+            "\\\\?\\C:\\x",
+            "\\\\.\\PIPE\\x",
+        ] {
+            assert!(matches!(
+                safe_join_windows("C:\\www", path),
+                Err(Error::EscapesRoot { .. })
+            ));
+        }
+        assert!(matches!(
+            safe_join_windows("\\\\?\\C:\\www", "a\\..\\..\\x"),
+            Err(Error::EscapesRoot { .. })
+        ));
+    }
+
+    #[test]
+    fn test_windows_root_not_absolute() {
+        assert!(safe_join_windows("C:www", "a").is_err());
+        assert!(safe_join_windows("www", "a").is_err());
+    }
+
+    #[test]
+    fn test_host() {
+        let root = abs("/srv");
+        assert_eq!(safe_join(&root, "a/../b").unwrap(), abs("/srv/b"));
+        assert!(matches!(
+            safe_join(&root, "a/../../b"),
+            Err(Error::EscapesRoot { .. })
+        ));
+        assert!(matches!(
+            safe_join(&root, "../srv/x"),
+            Err(Error::EscapesRoot { .. })
+        ));
+        assert!(matches!(
+            safe_join(&root, "a/../../srv/x"),
+            Err(Error::EscapesRoot { .. })
+        ));
+        assert!(matches!(
+            safe_join(abs("/srv/x"), &root),
+            Err(Error::EscapesRoot { .. })
+        ));
+        assert!(matches!(
+            safe_join("srv", "a"),
+            Err(Error::BaseNotAbsolute(_))
+        ));
+    }
+}
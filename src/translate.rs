@@ -0,0 +1,406 @@
+// Copyright (c) 2020-3 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+use crate::error::{Error, Result};
+use crate::path_clean::{clean_unix, clean_windows};
+use crate::windows_prefix::WindowsPrefix;
+use std::path::PathBuf;
+
+/// Table mapping Windows drive letters to Unix directories, used by
+/// [`to_unix`] and [`to_windows`]
+///
+/// Drives are mapped to a subdirectory of a common prefix named after the
+/// lowercase drive letter, e.g. `C:\` to `/mnt/c`, unless a directory is
+/// given explicitly for the drive.
+///
+/// ```rust
+/// use joat_path::{to_unix, to_windows, DriveMapping};
+///
+/// assert_eq!(to_unix("C:\\aa\\bb", &DriveMapping::wsl()).unwrap(), "/mnt/c/aa/bb");
+/// assert_eq!(to_unix("C:\\aa\\bb", &DriveMapping::msys()).unwrap(), "/c/aa/bb");
+/// assert_eq!(to_unix("C:\\aa\\bb", &DriveMapping::cygwin()).unwrap(), "/cygdrive/c/aa/bb");
+///
+/// let mapping = DriveMapping::new().drive('D', "/data");
+/// assert_eq!(to_windows("/data/aa", &mapping).unwrap(), "D:\\aa");
+/// assert!(to_windows("/mnt/c/aa", &mapping).is_err());
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DriveMapping {
+    prefix: Option<String>,
+    drives: Vec<(u8, String)>,
+}
+
+impl DriveMapping {
+    /// Mapping with no drives
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            prefix: None,
+            drives: Vec::new(),
+        }
+    }
+
+    /// Mapping of drives to subdirectories of the given directory
+    ///
+    /// # Arguments
+    ///
+    /// * `prefix` - Unix directory containing drive directories
+    #[must_use]
+    pub fn with_prefix(prefix: &str) -> Self {
+        Self {
+            prefix: Some(clean_unix(prefix)),
+            drives: Vec::new(),
+        }
+    }
+
+    /// Mapping used by WSL, e.g. `C:\` to `/mnt/c`
+    #[must_use]
+    pub fn wsl() -> Self {
+        Self::with_prefix("/mnt")
+    }
+
+    /// Mapping used by MSYS2 and Git Bash, e.g. `C:\` to `/c`
+    #[must_use]
+    pub fn msys() -> Self {
+        Self::with_prefix("/")
+    }
+
+    /// Mapping used by Cygwin, e.g. `C:\` to `/cygdrive/c`
+    #[must_use]
+    pub fn cygwin() -> Self {
+        Self::with_prefix("/cygdrive")
+    }
+
+    /// Map drive to the given Unix directory, overriding the common prefix
+    ///
+    /// # Arguments
+    ///
+    /// * `drive` - Drive letter
+    /// * `dir` - Unix directory
+    ///
+    /// # Panics
+    ///
+    /// Panics if `drive` is not an ASCII letter.
+    #[must_use]
+    pub fn drive(mut self, drive: char, dir: &str) -> Self {
+        assert!(drive.is_ascii_alphabetic(), "invalid drive letter {drive}");
+        let drive = drive.to_ascii_uppercase() as u8;
+        self.drives.retain(|(d, _)| *d != drive);
+        self.drives.push((drive, clean_unix(dir)));
+        self
+    }
+
+    fn drive_dir(&self, drive: u8) -> Option<String> {
+        let drive = drive.to_ascii_uppercase();
+        if let Some((_, dir)) = self.drives.iter().find(|(d, _)| *d == drive) {
+            return Some(dir.clone());
+        }
+        self.prefix.as_ref().map(|prefix| {
+            format!(
+                "{}/{}",
+                prefix.trim_end_matches('/'),
+                char::from(drive.to_ascii_lowercase())
+            )
+        })
+    }
+
+    fn split_drive<'a>(&self, path: &'a str) -> Option<(u8, &'a str)> {
+        let explicit = self
+            .drives
+            .iter()
+            .filter_map(|(drive, dir)| strip_dir(path, dir).map(|rest| (dir.len(), *drive, rest)))
+            .max_by_key(|(len, _, _)| *len);
+        if let Some((_, drive, rest)) = explicit {
+            return Some((drive, rest));
+        }
+
+        let rest = strip_dir(path, self.prefix.as_ref()?)?.trim_start_matches('/');
+        match rest.as_bytes() {
+            [drive] | [drive, b'/', ..] if drive.is_ascii_alphabetic() => {
+                Some((drive.to_ascii_uppercase(), &rest[1..]))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Translate Windows path to Unix path without accessing the file system
+///
+/// Absolute paths on a drive are translated via `mapping` and UNC paths
+/// are translated to paths beginning with `//`. Fails if a drive is not
+/// mapped, if the path is relative to the current directory of a drive
+/// or to the root of the current drive or if a verbatim path contains
+/// names such as `..` that have a different meaning on Unix.
+///
+/// # Arguments
+///
+/// * `path` - Windows path
+/// * `mapping` - Drive mapping
+pub fn to_unix(path: &str, mapping: &DriveMapping) -> Result<String> {
+    let not_translatable = || Error::NotTranslatable(PathBuf::from(path));
+
+    let path_clean = clean_windows(path);
+    let out = match WindowsPrefix::split(&path_clean) {
+        Some((WindowsPrefix::VerbatimDisk(_) | WindowsPrefix::VerbatimUnc(..), rest))
+            if !is_literal_on_unix(rest) =>
+        {
+            return Err(not_translatable())
+        }
+        Some((WindowsPrefix::Disk(drive) | WindowsPrefix::VerbatimDisk(drive), rest))
+            if rest.is_empty() || rest.starts_with(['\\', '/']) =>
+        {
+            if rest.is_empty() && !path_clean.starts_with(r"\\?\") {
+                return Err(not_translatable());
+            }
+            let dir = mapping.drive_dir(drive).ok_or_else(not_translatable)?;
+            format!("{dir}/{}", rest.replace('\\', "/"))
+        }
+        Some((
+            WindowsPrefix::Unc(server, share) | WindowsPrefix::VerbatimUnc(server, share),
+            rest,
+        )) if !share.is_empty() => {
+            format!("//{server}/{share}{}", rest.replace('\\', "/"))
+        }
+        Some(_) => return Err(not_translatable()),
+        None if path_clean.starts_with(['\\', '/']) => return Err(not_translatable()),
+        None => path_clean.replace('\\', "/"),
+    };
+
+    match out.strip_prefix("//") {
+        Some(rest) => Ok(format!("/{}", clean_unix(&format!("/{rest}")))),
+        None => Ok(clean_unix(&out)),
+    }
+}
+
+/// Translate Unix path to Windows path without accessing the file system
+///
+/// Absolute paths are translated via `mapping` and paths beginning with
+/// exactly two separators are translated to UNC paths. Fails if an
+/// absolute path does not lie within a mapped directory or if a name
+/// contains a backslash.
+///
+/// # Arguments
+///
+/// * `path` - Unix path
+/// * `mapping` - Drive mapping
+pub fn to_windows(path: &str, mapping: &DriveMapping) -> Result<String> {
+    let not_translatable = || Error::NotTranslatable(PathBuf::from(path));
+
+    // Backslash is part of a name on Unix but a separator on Windows
+    if path.contains('\\') {
+        return Err(not_translatable());
+    }
+
+    if let Some(rest) = path.strip_prefix("//") {
+        if !rest.is_empty() && !rest.starts_with('/') {
+            return match WindowsPrefix::split(&path.replace('/', "\\")) {
+                Some((WindowsPrefix::Unc(_, share), _)) if !share.is_empty() => {
+                    Ok(clean_windows(&path.replace('/', "\\")))
+                }
+                _ => Err(not_translatable()),
+            };
+        }
+    }
+
+    let path_clean = clean_unix(path);
+    if !path_clean.starts_with('/') {
+        // Relative path must not parse as a path on a drive, e.g. `c:aa`
+        let out = path_clean.replace('/', "\\");
+        return Ok(if WindowsPrefix::split(&out).is_some() {
+            format!(".\\{out}")
+        } else {
+            out
+        });
+    }
+
+    let (drive, rest) = mapping
+        .split_drive(&path_clean)
+        .ok_or_else(not_translatable)?;
+    Ok(format!(
+        "{}:\\{}",
+        char::from(drive),
+        rest.trim_start_matches('/').replace('/', "\\")
+    ))
+}
+
+// Verbatim paths are not cleaned, so `.` and `..` are names and `/` is part
+// of a name, none of which survive translation to Unix
+pub fn is_literal_on_unix(rest: &str) -> bool {
+    !rest.contains('/') && rest.split('\\').all(|s| s != "." && s != "..")
+}
+
+fn strip_dir<'a>(path: &'a str, dir: &str) -> Option<&'a str> {
+    let rest = path.strip_prefix(dir)?;
+    if rest.is_empty() || rest.starts_with('/') || dir.ends_with('/') {
+        Some(rest)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{to_unix, to_windows, DriveMapping};
+    use crate::error::Error;
+
+    #[test]
+    fn test_to_unix() {
+        let tests = vec![
+            ("C:\\", "/mnt/c", "/c", "/cygdrive/c"),
+            (
+                "C:\\aa\\bb",
+                "/mnt/c/aa/bb",
+                "/c/aa/bb",
+                "/cygdrive/c/aa/bb",
+            ),
+            ("d:/aa/../bb/", "/mnt/d/bb", "/d/bb", "/cygdrive/d/bb"),
+            ("\\\\?\\C:\\aa", "/mnt/c/aa", "/c/aa", "/cygdrive/c/aa"),
+            ("aa\\.\\bb", "aa/bb", "aa/bb", "aa/bb"),
+            ("..\\aa", "../aa", "../aa", "../aa"),
+            (
+                "\\\\srv\\share\\aa",
+                "//srv/share/aa",
+                "//srv/share/aa",
+                "//srv/share/aa",
+            ),
+            (
+                "\\\\srv\\share\\..",
+                "//srv/share",
+                "//srv/share",
+                "//srv/share",
+            ),
+        ];
+
+        for test in tests {
+            assert_eq!(to_unix(test.0, &DriveMapping::wsl()).unwrap(), test.1);
+            assert_eq!(to_unix(test.0, &DriveMapping::msys()).unwrap(), test.2);
+            assert_eq!(to_unix(test.0, &DriveMapping::cygwin()).unwrap(), test.3);
+        }
+    }
+
+    #[test]
+    fn test_to_unix_fails() {
+        let mapping = DriveMapping::wsl();
+        for path in [
+            "C:aa",
+            "C:",
+            "\\aa",
+            "\\\\.\\PIPE\\aa",
+            "\\\\srv",
+            "\\\\?\\C:\\aa\\..\\bb",
+            "\\\\?\\C:\\aa\\.",
+            "\\\\?\\C:\\aa/bb",
+            "\\\\?\\UNC\\srv\\share\\..",
+        ] {
+            assert!(matches!(
+                to_unix(path, &mapping),
+                Err(Error::NotTranslatable(_))
+            ));
+        }
+        assert!(to_unix("C:\\aa", &DriveMapping::new()).is_err());
+    }
+
+    #[test]
+    fn test_to_windows() {
+        let tests = vec![
+            ("/mnt/c", "/c", "/cygdrive/c", "C:\\"),
+            (
+                "/mnt/c/aa/bb",
+                "/c/aa/bb",
+                "/cygdrive/c/aa/bb",
+                "C:\\aa\\bb",
+            ),
+            (
+                "/mnt/d/aa/../bb/",
+                "/D/aa/../bb/",
+                "/cygdrive/d//bb",
+                "D:\\bb",
+            ),
+            ("aa/./bb", "aa/./bb", "aa/./bb", "aa\\bb"),
+            ("x/../c:", "x/../c:", "x/../c:", ".\\c:"),
+            ("c:aa/bb", "c:aa/bb", "c:aa/bb", ".\\c:aa\\bb"),
+            (
+                "//srv/share/aa",
+                "//srv/share/aa",
+                "//srv/share/aa",
+                "\\\\srv\\share\\aa",
+            ),
+        ];
+
+        for test in tests {
+            assert_eq!(to_windows(test.0, &DriveMapping::wsl()).unwrap(), test.3);
+            assert_eq!(to_windows(test.1, &DriveMapping::msys()).unwrap(), test.3);
+            assert_eq!(to_windows(test.2, &DriveMapping::cygwin()).unwrap(), test.3);
+        }
+    }
+
+    #[test]
+    fn test_to_windows_fails() {
+        let mapping = DriveMapping::wsl();
+        for path in [
+            "/",
+            "/mnt",
+            "/mnt/cc",
+            "/usr/bin",
+            "/mnt/../c",
+            "//srv",
+            "a\\b",
+            "/mnt/c/a\\b",
+            "//srv/share/a\\b",
+        ] {
+            assert!(matches!(
+                to_windows(path, &mapping),
+                Err(Error::NotTranslatable(_))
+            ));
+        }
+        assert!(to_windows("/c/aa", &mapping).is_err());
+        assert!(to_windows("/aa", &DriveMapping::msys()).is_err());
+    }
+
+    #[test]
+    fn test_explicit_drives() {
+        let mapping = DriveMapping::wsl()
+            .drive('d', "/data/")
+            .drive('E', "/data/ee");
+        assert_eq!(to_unix("D:\\aa", &mapping).unwrap(), "/data/aa");
+        assert_eq!(to_unix("e:\\aa", &mapping).unwrap(), "/data/ee/aa");
+        assert_eq!(to_unix("C:\\aa", &mapping).unwrap(), "/mnt/c/aa");
+        assert_eq!(to_windows("/data/aa", &mapping).unwrap(), "D:\\aa");
+        assert_eq!(to_windows("/data/ee/aa", &mapping).unwrap(), "E:\\aa");
+        assert_eq!(to_windows("/data/eee", &mapping).unwrap(), "D:\\eee");
+        assert_eq!(to_windows("/mnt/d/aa", &mapping).unwrap(), "D:\\aa");
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mapping = DriveMapping::msys();
+        for path in [
+            "C:\\aa\\bb",
+            "Z:\\",
+            "aa\\bb",
+            "\\\\srv\\share\\aa",
+            ".\\c:aa",
+        ] {
+            let unix = to_unix(path, &mapping).unwrap();
+            assert_eq!(to_windows(&unix, &mapping).unwrap(), path);
+        }
+    }
+}
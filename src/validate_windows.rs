@@ -0,0 +1,414 @@
+// Copyright (c) 2020-3 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+use crate::flavor::{PathFlavor, PrefixKind, WindowsFlavor};
+use crate::path_clean::clean_windows;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+/// Maximum length of path component in UTF-16 code units
+pub const MAX_COMPONENT_LEN: usize = 255;
+
+/// Maximum length of path in UTF-16 code units, i.e. `MAX_PATH` less the
+/// terminating null
+pub const MAX_PATH_LEN: usize = 259;
+
+/// Maximum length of path with verbatim prefix `\\?\` in UTF-16 code units
+pub const MAX_VERBATIM_PATH_LEN: usize = 32_767;
+
+const RESERVED_NAMES: [&str; 6] = ["AUX", "CON", "CONIN$", "CONOUT$", "NUL", "PRN"];
+
+/// Reason why a file name is not valid on Windows
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum WindowsNameError {
+    /// Name is empty
+    Empty,
+
+    /// Name is a reserved device name such as `CON` or `COM1`, possibly
+    /// followed by an extension
+    ReservedName,
+
+    /// Name contains one of `<>:"/\|?*` or a control character
+    InvalidChar(char),
+
+    /// Name ends with `.`
+    TrailingDot,
+
+    /// Name ends with a space
+    TrailingSpace,
+
+    /// Name is longer than [`MAX_COMPONENT_LEN`] UTF-16 code units
+    TooLong(usize),
+}
+
+/// Reason why a path is not valid on Windows
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum WindowsPathError {
+    /// Component of path is not a valid file name
+    Component {
+        component: String,
+        error: WindowsNameError,
+    },
+
+    /// Path is longer than [`MAX_PATH_LEN`], or [`MAX_VERBATIM_PATH_LEN`]
+    /// for verbatim paths, UTF-16 code units
+    TooLong(usize),
+}
+
+impl Display for WindowsNameError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Empty => write!(f, "Name is empty"),
+            Self::ReservedName => write!(f, "Name is reserved"),
+            Self::InvalidChar(c) => write!(f, "Name contains invalid character {c:?}"),
+            Self::TrailingDot => write!(f, "Name ends with a dot"),
+            Self::TrailingSpace => write!(f, "Name ends with a space"),
+            Self::TooLong(len) => write!(
+                f,
+                "Name length {len} exceeds maximum of {MAX_COMPONENT_LEN}"
+            ),
+        }
+    }
+}
+
+impl Display for WindowsPathError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Component { component, error } => write!(f, "{error}: {component}"),
+            Self::TooLong(len) => write!(f, "Path length {len} exceeds maximum"),
+        }
+    }
+}
+
+impl std::error::Error for WindowsNameError {}
+
+impl std::error::Error for WindowsPathError {}
+
+/// Check that file name is valid on Windows, reporting every problem found
+///
+/// ```rust
+/// use joat_path::{validate_windows_component, WindowsNameError};
+/// assert!(validate_windows_component("readme.txt").is_ok());
+/// assert_eq!(
+///     validate_windows_component("aux.txt").unwrap_err(),
+///     [WindowsNameError::ReservedName]
+/// );
+/// assert_eq!(
+///     validate_windows_component("a:b.").unwrap_err(),
+///     [WindowsNameError::InvalidChar(':'), WindowsNameError::TrailingDot]
+/// );
+/// ```
+pub fn validate_windows_component(name: &str) -> Result<(), Vec<WindowsNameError>> {
+    let mut errors = Vec::new();
+
+    if name.is_empty() {
+        errors.push(WindowsNameError::Empty);
+    }
+
+    if is_reserved_name(name) {
+        errors.push(WindowsNameError::ReservedName);
+    }
+
+    errors.extend(
+        name.chars()
+            .filter(|&c| (c.is_ascii_control() && c != '\x7f') || "<>:\"/\\|?*".contains(c))
+            .map(WindowsNameError::InvalidChar),
+    );
+
+    if name.ends_with('.') {
+        errors.push(WindowsNameError::TrailingDot);
+    } else if name.ends_with(' ') {
+        errors.push(WindowsNameError::TrailingSpace);
+    }
+
+    let len = name.encode_utf16().count();
+    if len > MAX_COMPONENT_LEN {
+        errors.push(WindowsNameError::TooLong(len));
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Check that every component of path is a valid file name on Windows and
+/// that the path is not too long, reporting every problem found
+///
+/// The path is cleaned with [`crate::clean_windows`] first, so `.` and `..`
+/// elements and redundant separators are not reported. Components following
+/// a verbatim prefix are checked in the same way since most programs cannot
+/// access such files.
+///
+/// ```rust
+/// use joat_path::{validate_windows_path, WindowsNameError, WindowsPathError};
+/// assert!(validate_windows_path("C:\\aa\\..\\bb/./cc.txt").is_ok());
+/// assert_eq!(
+///     validate_windows_path("C:\\con\\bar ").unwrap_err(),
+///     [
+///         WindowsPathError::Component {
+///             component: String::from("con"),
+///             error: WindowsNameError::ReservedName,
+///         },
+///         WindowsPathError::Component {
+///             component: String::from("bar "),
+///             error: WindowsNameError::TrailingSpace,
+///         },
+///     ]
+/// );
+/// ```
+pub fn validate_windows_path(path: &str) -> Result<(), Vec<WindowsPathError>> {
+    let path = clean_windows(path);
+    let (prefix, rest) = WindowsFlavor::split_prefix(&path);
+
+    let mut errors = rest
+        .split('\\')
+        .filter(|component| !matches!(*component, "" | "." | ".."))
+        .flat_map(|component| {
+            validate_windows_component(component)
+                .err()
+                .unwrap_or_default()
+                .into_iter()
+                .map(move |error| WindowsPathError::Component {
+                    component: String::from(component),
+                    error,
+                })
+        })
+        .collect::<Vec<_>>();
+
+    let max_len = match prefix {
+        Some(prefix) if prefix.kind == PrefixKind::Verbatim => MAX_VERBATIM_PATH_LEN,
+        _ => MAX_PATH_LEN,
+    };
+    let len = path.encode_utf16().count();
+    if len > max_len {
+        errors.push(WindowsPathError::TooLong(len));
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+fn is_reserved_name(name: &str) -> bool {
+    // Extension and trailing spaces are ignored when matching device names
+    let stem = name.split('.').next().unwrap_or(name).trim_end_matches(' ');
+    if RESERVED_NAMES
+        .iter()
+        .any(|reserved| stem.eq_ignore_ascii_case(reserved))
+    {
+        return true;
+    }
+
+    let mut chars = stem.chars();
+    let device = chars.by_ref().take(3).collect::<String>();
+    let is_port = device.eq_ignore_ascii_case("COM") || device.eq_ignore_ascii_case("LPT");
+    let digit = chars.next();
+    is_port
+        && chars.next().is_none()
+        && matches!(digit, Some('0'..='9' | '\u{b9}' | '\u{b2}' | '\u{b3}'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        validate_windows_component, validate_windows_path, WindowsNameError, WindowsPathError,
+        MAX_COMPONENT_LEN,
+    };
+    use crate::clean_windows;
+
+    #[test]
+    fn test_valid_component() {
+        for name in [
+            "a",
+            "readme.txt",
+            ".gitignore",
+            "a b",
+            "console",
+            "con_",
+            "xcon",
+            "com10",
+            "lpt",
+            "COM\u{2074}",
+            "nul-txt",
+            "\u{7f}",
+            "日本語",
+        ] {
+            assert_eq!(validate_windows_component(name), Ok(()), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_reserved_name() {
+        for name in [
+            "CON",
+            "con",
+            "Aux.txt",
+            "nul.tar.gz",
+            "PRN .txt",
+            "COM1",
+            "com0.log",
+            "LPT9",
+            "COM\u{b9}",
+            "lpt\u{b2}.txt",
+            "LPT\u{b3}",
+            "CONIN$",
+            "conout$.x",
+        ] {
+            assert_eq!(
+                validate_windows_component(name),
+                Err(vec![WindowsNameError::ReservedName]),
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_chars() {
+        assert_eq!(
+            validate_windows_component("a<b>c:d\"e/f\\g|h?i*j\u{1}k\tl"),
+            Err("<>:\"/\\|?*\u{1}\t"
+                .chars()
+                .map(WindowsNameError::InvalidChar)
+                .collect())
+        );
+    }
+
+    #[test]
+    fn test_trailing() {
+        assert_eq!(
+            validate_windows_component("foo."),
+            Err(vec![WindowsNameError::TrailingDot])
+        );
+        assert_eq!(
+            validate_windows_component("bar "),
+            Err(vec![WindowsNameError::TrailingSpace])
+        );
+        assert_eq!(
+            validate_windows_component(".."),
+            Err(vec![WindowsNameError::TrailingDot])
+        );
+        assert_eq!(
+            validate_windows_component("CON."),
+            Err(vec![
+                WindowsNameError::ReservedName,
+                WindowsNameError::TrailingDot
+            ])
+        );
+    }
+
+    #[test]
+    fn test_length() {
+        let name = "a".repeat(MAX_COMPONENT_LEN);
+        assert_eq!(validate_windows_component(&name), Ok(()));
+
+        let name = "a".repeat(MAX_COMPONENT_LEN + 1);
+        assert_eq!(
+            validate_windows_component(&name),
+            Err(vec![WindowsNameError::TooLong(256)])
+        );
+
+        let name = "\u{1f600}".repeat(128);
+        assert_eq!(
+            validate_windows_component(&name),
+            Err(vec![WindowsNameError::TooLong(256)])
+        );
+
+        assert_eq!(
+            validate_windows_component(""),
+            Err(vec![WindowsNameError::Empty])
+        );
+    }
+
+    #[test]
+    fn test_path() {
+        for path in [
+            "C:\\aa\\bb.txt",
+            "aa/bb/",
+            "C:\\aa\\con\\..\\bb",
+            "\\\\srv\\share\\aa",
+            "\\\\.\\PIPE\\aa",
+            "..\\..\\aa",
+            "",
+        ] {
+            assert_eq!(validate_windows_path(path), Ok(()), "{path}");
+        }
+
+        let component = |component: &str, error| WindowsPathError::Component {
+            component: String::from(component),
+            error,
+        };
+
+        assert_eq!(
+            validate_windows_path("C:\\aux.txt\\ok\\a:b.\\nul"),
+            Err(vec![
+                component("aux.txt", WindowsNameError::ReservedName),
+                component("a:b.", WindowsNameError::InvalidChar(':')),
+                component("a:b.", WindowsNameError::TrailingDot),
+                component("nul", WindowsNameError::ReservedName),
+            ])
+        );
+        assert_eq!(
+            validate_windows_path("\\\\?\\C:\\aa/bb\\com1"),
+            Err(vec![
+                component("aa/bb", WindowsNameError::InvalidChar('/')),
+                component("com1", WindowsNameError::ReservedName),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_path_length() {
+        let name = "a".repeat(100);
+        let path = format!("C:\\{name}\\{name}\\{name}");
+        assert_eq!(
+            validate_windows_path(&path),
+            Err(vec![WindowsPathError::TooLong(305)])
+        );
+        assert_eq!(validate_windows_path(&format!("\\\\?\\{path}")), Ok(()));
+
+        let path = format!("C:\\{}", "a".repeat(300));
+        assert_eq!(
+            validate_windows_path(&path),
+            Err(vec![
+                WindowsPathError::Component {
+                    component: "a".repeat(300),
+                    error: WindowsNameError::TooLong(300),
+                },
+                WindowsPathError::TooLong(303),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_clean_then_validate() {
+        let path = clean_windows("C:/aa/./bb/../cc.");
+        assert_eq!(path, "C:\\aa\\cc.");
+        assert_eq!(
+            validate_windows_path(&path),
+            validate_windows_path("C:/aa/./bb/../cc.")
+        );
+    }
+}
@@ -0,0 +1,235 @@
+// Copyright (c) 2020-3 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+use crate::absolute_path::absolute_path_windows_with_drives;
+use crate::error::{Error, Result};
+use crate::flavor::{PathFlavor, PrefixKind, WindowsFlavor};
+use crate::path_clean::clean_windows;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Model of the Windows current directory: a current drive together with
+/// a separate current directory for each drive
+///
+/// ```rust
+/// use joat_path::WindowsCwd;
+///
+/// let mut cwd = WindowsCwd::new("C:\\Users\\me").unwrap();
+/// cwd.set_drive_dir("D:\\data").unwrap();
+/// assert_eq!(cwd.resolve("aa").unwrap(), "C:\\Users\\me\\aa");
+/// assert_eq!(cwd.resolve("\\aa").unwrap(), "C:\\aa");
+/// assert_eq!(cwd.resolve("D:aa").unwrap(), "D:\\data\\aa");
+/// assert_eq!(cwd.resolve("E:aa").unwrap(), "E:\\aa");
+///
+/// cwd.set_current_dir("D:..\\work").unwrap();
+/// assert_eq!(cwd.current_drive(), 'D');
+/// assert_eq!(cwd.resolve("aa").unwrap(), "D:\\work\\aa");
+/// assert_eq!(cwd.resolve("C:aa").unwrap(), "C:\\Users\\me\\aa");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WindowsCwd {
+    drive: char,
+    dirs: BTreeMap<char, String>,
+}
+
+impl WindowsCwd {
+    /// Current directory model with the given current directory
+    ///
+    /// Fails with [`Error::UncCurrentDir`] if the directory is on a UNC
+    /// share or device or has a verbatim prefix.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - Current directory (must be absolute and begin with a drive letter)
+    pub fn new(dir: &str) -> Result<Self> {
+        let (drive, dir) = drive_dir(dir)?;
+        Ok(Self {
+            drive,
+            dirs: BTreeMap::from([(drive, dir)]),
+        })
+    }
+
+    /// Current drive letter in upper case
+    #[must_use]
+    pub const fn current_drive(&self) -> char {
+        self.drive
+    }
+
+    /// Current directory of current drive
+    #[must_use]
+    pub fn current_dir(&self) -> String {
+        self.drive_dir(self.drive)
+    }
+
+    /// Current directory of drive, which is the root of the drive if the
+    /// drive's current directory has not been set
+    ///
+    /// # Arguments
+    ///
+    /// * `drive` - Drive letter
+    #[must_use]
+    pub fn drive_dir(&self, drive: char) -> String {
+        let drive = drive.to_ascii_uppercase();
+        self.dirs
+            .get(&drive)
+            .cloned()
+            .unwrap_or_else(|| format!("{drive}:\\"))
+    }
+
+    /// Change current directory, and current drive if the path names a
+    /// different drive, like `SetCurrentDirectory`
+    ///
+    /// Fails with [`Error::UncCurrentDir`] if the path resolves to a UNC
+    /// share, device or verbatim path since only drives have current
+    /// directories in this model.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path resolved against the current directory
+    pub fn set_current_dir(&mut self, path: &str) -> Result<()> {
+        let (drive, dir) = drive_dir(&self.resolve(path)?)?;
+        self.drive = drive;
+        self.dirs.insert(drive, dir);
+        Ok(())
+    }
+
+    /// Change current directory of a drive without changing current drive
+    ///
+    /// Fails with [`Error::UncCurrentDir`] if the directory is on a UNC
+    /// share or device or has a verbatim prefix.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - Directory (must be absolute and begin with a drive letter)
+    pub fn set_drive_dir(&mut self, dir: &str) -> Result<()> {
+        let (drive, dir) = drive_dir(dir)?;
+        self.dirs.insert(drive, dir);
+        Ok(())
+    }
+
+    /// Normalize a target path to an absolute path relative to the current
+    /// directory of the current drive or, for paths such as `D:aa`, of the
+    /// drive named by the path
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path
+    pub fn resolve(&self, path: &str) -> Result<String> {
+        absolute_path_windows_with_drives(&self.current_dir(), path, &self.dirs)
+    }
+}
+
+fn drive_dir(dir: &str) -> Result<(char, String)> {
+    match WindowsFlavor::split_prefix(dir) {
+        (Some(prefix), rest)
+            if prefix.kind == PrefixKind::Drive && WindowsFlavor::is_rooted(rest) =>
+        {
+            let drive = prefix.text.as_bytes()[0].to_ascii_uppercase();
+            Ok((char::from(drive), clean_windows(dir)))
+        }
+        (Some(prefix), _) if prefix.kind != PrefixKind::Drive => {
+            Err(Error::UncCurrentDir(PathBuf::from(dir)))
+        }
+        _ => Err(Error::BaseNotAbsolute(PathBuf::from(dir))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WindowsCwd;
+    use crate::error::Error;
+
+    #[test]
+    fn test_resolve() {
+        let mut cwd = WindowsCwd::new("c:/aa/./bb").unwrap();
+        cwd.set_drive_dir("D:\\cc\\dd\\").unwrap();
+
+        let tests = vec![
+            ("", "c:\\aa\\bb"),
+            ("ee", "c:\\aa\\bb\\ee"),
+            ("..\\..\\..", "c:\\"),
+            ("\\ee", "c:\\ee"),
+            ("/ee", "c:\\ee"),
+            ("C:ee", "c:\\aa\\bb\\ee"),
+            ("D:ee", "D:\\cc\\dd\\ee"),
+            ("d:..\\ee", "D:\\cc\\ee"),
+            ("D:", "D:\\cc\\dd"),
+            ("D:\\ee", "D:\\ee"),
+            ("E:ee", "E:\\ee"),
+            ("\\\\srv\\share\\ee", "\\\\srv\\share\\ee"),
+        ];
+
+        for test in tests {
+            assert_eq!(cwd.resolve(test.0).unwrap(), test.1);
+        }
+    }
+
+    #[test]
+    fn test_set_current_dir() {
+        let mut cwd = WindowsCwd::new("C:\\aa").unwrap();
+        assert_eq!(cwd.current_drive(), 'C');
+
+        cwd.set_current_dir("bb").unwrap();
+        assert_eq!(cwd.current_dir(), "C:\\aa\\bb");
+
+        cwd.set_current_dir("d:cc").unwrap();
+        assert_eq!(cwd.current_drive(), 'D');
+        assert_eq!(cwd.current_dir(), "d:\\cc");
+        assert_eq!(cwd.drive_dir('c'), "C:\\aa\\bb");
+
+        cwd.set_current_dir("\\").unwrap();
+        assert_eq!(cwd.current_dir(), "d:\\");
+        assert_eq!(cwd.resolve("C:").unwrap(), "C:\\aa\\bb");
+
+        cwd.set_current_dir("C:").unwrap();
+        assert_eq!(cwd.current_drive(), 'C');
+        assert_eq!(cwd.current_dir(), "C:\\aa\\bb");
+        assert_eq!(cwd.drive_dir('E'), "E:\\");
+
+        assert!(matches!(
+            cwd.set_current_dir("\\\\srv\\share"),
+            Err(Error::UncCurrentDir(_))
+        ));
+        assert!(matches!(
+            cwd.set_drive_dir("\\\\srv\\share\\aa"),
+            Err(Error::UncCurrentDir(_))
+        ));
+        assert_eq!(cwd.current_dir(), "C:\\aa\\bb");
+    }
+
+    #[test]
+    fn test_not_absolute() {
+        for dir in ["\\\\srv\\share", "\\\\?\\C:\\aa"] {
+            assert!(matches!(WindowsCwd::new(dir), Err(Error::UncCurrentDir(_))));
+        }
+
+        for dir in ["aa", "\\aa", "C:aa"] {
+            assert!(matches!(
+                WindowsCwd::new(dir),
+                Err(Error::BaseNotAbsolute(_))
+            ));
+        }
+
+        let mut cwd = WindowsCwd::new("C:\\").unwrap();
+        assert!(cwd.set_drive_dir("D:aa").is_err());
+        assert_eq!(cwd, WindowsCwd::new("C:\\").unwrap());
+    }
+}
@@ -0,0 +1,183 @@
+// Copyright (c) 2020-3 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+use crate::case_fold::fold_case;
+use crate::path_clean::clean_windows;
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::hash::{Hash, Hasher};
+
+/// Windows path that compares, orders and hashes the way Windows compares
+/// paths: after cleaning with [`crate::clean_windows`], treating `/` and
+/// `\` alike and ignoring case
+///
+/// Case is ignored by Unicode simple case folding.
+///
+/// ```rust
+/// use joat_path::WindowsPathKey;
+/// use std::collections::HashSet;
+///
+/// let key = WindowsPathKey::new("C:\\Foo");
+/// assert_eq!(key, WindowsPathKey::new("c:/foo/"));
+/// assert_eq!(key, WindowsPathKey::new("C:\\FOO\\bar\\.."));
+/// assert_ne!(key, WindowsPathKey::new("C:\\Foo\\bar"));
+///
+/// let keys = ["C:\\Foo", "c:/foo/", "C:\\ΣΑΣ", "c:\\σας"]
+///     .into_iter()
+///     .map(WindowsPathKey::new)
+///     .collect::<HashSet<_>>();
+/// assert_eq!(keys.len(), 2);
+/// ```
+#[derive(Clone, Debug)]
+pub struct WindowsPathKey {
+    path: String,
+    folded: String,
+}
+
+impl WindowsPathKey {
+    /// Create key from path
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Windows path
+    #[must_use]
+    pub fn new(path: &str) -> Self {
+        let path = clean_windows(path);
+        let folded = path.chars().map(fold_case).collect();
+        Self { path, folded }
+    }
+
+    /// Cleaned path with its original case
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.path
+    }
+
+    /// Convert into cleaned path with its original case
+    #[must_use]
+    pub fn into_string(self) -> String {
+        self.path
+    }
+}
+
+impl From<&str> for WindowsPathKey {
+    fn from(value: &str) -> Self {
+        Self::new(value)
+    }
+}
+
+impl Display for WindowsPathKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", self.path)
+    }
+}
+
+impl PartialEq for WindowsPathKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.folded == other.folded
+    }
+}
+
+impl Eq for WindowsPathKey {}
+
+impl PartialOrd for WindowsPathKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for WindowsPathKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.folded.cmp(&other.folded)
+    }
+}
+
+impl Hash for WindowsPathKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.folded.hash(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WindowsPathKey;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_eq() {
+        let tests = vec![
+            ("C:\\Foo", "c:\\foo\\"),
+            ("C:/Foo/./Bar", "c:\\FOO\\bar"),
+            ("\\\\Srv\\Share\\A", "//srv/SHARE/a"),
+            ("Straße", "STRAẞE"),
+            ("ΣΑΣ", "σας"),
+            ("Ǆ", "ǆ"),
+            ("ǅ", "ǆ"),
+            ("\u{212a}", "k"),
+            ("\u{1fd3}", "\u{390}"),
+            ("\u{fb05}", "\u{fb06}"),
+            ("\u{ab70}", "\u{13a0}"),
+            ("", "."),
+        ];
+
+        for test in tests {
+            assert_eq!(WindowsPathKey::new(test.0), WindowsPathKey::new(test.1));
+        }
+    }
+
+    #[test]
+    fn test_ne() {
+        let tests = vec![
+            ("C:\\Foo", "D:\\Foo"),
+            ("C:\\Foo", "C:Foo"),
+            ("Foo", "Foo "),
+            ("Straße", "STRASSE"),
+            ("ı", "i"),
+            ("\\\\?\\C:\\Foo\\", "\\\\?\\C:\\Foo"),
+        ];
+
+        for test in tests {
+            assert_ne!(WindowsPathKey::new(test.0), WindowsPathKey::new(test.1));
+        }
+    }
+
+    #[test]
+    fn test_ord() {
+        let mut keys = ["c:\\B", "C:\\a", "C:\\c"].map(WindowsPathKey::new);
+        keys.sort();
+        assert_eq!(
+            keys.map(WindowsPathKey::into_string),
+            ["C:\\a", "c:\\B", "C:\\c"]
+        );
+    }
+
+    #[test]
+    fn test_hash_map_key() {
+        let mut map = HashMap::new();
+        map.insert(WindowsPathKey::new("C:\\Users\\Me"), 1);
+        *map.entry(WindowsPathKey::new("c:/users/me/")).or_insert(0) += 1;
+        assert_eq!(map.len(), 1);
+
+        let (key, value) = map.into_iter().next().unwrap();
+        assert_eq!(key.as_str(), "C:\\Users\\Me");
+        assert_eq!(value, 2);
+    }
+}
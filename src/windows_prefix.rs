@@ -0,0 +1,240 @@
+// Copyright (c) 2020-3 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+/// Prefix of a Windows path, analogous to [`std::path::Prefix`] but
+/// available on all host platforms
+///
+/// Windows path prefixes are parsed lexically from strings so that
+/// Windows paths can be inspected on any host operating system.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WindowsPrefix<'a> {
+    /// Verbatim prefix, e.g. `\\?\cat_pics`
+    Verbatim(&'a str),
+
+    /// Verbatim UNC prefix, e.g. `\\?\UNC\server\share`
+    VerbatimUnc(&'a str, &'a str),
+
+    /// Verbatim disk prefix, e.g. `\\?\C:`
+    VerbatimDisk(u8),
+
+    /// Device namespace prefix, e.g. `\\.\PIPE`
+    DeviceNs(&'a str),
+
+    /// UNC share prefix, e.g. `\\server\share`
+    Unc(&'a str, &'a str),
+
+    /// Disk prefix, e.g. `C:`
+    Disk(u8),
+}
+
+impl<'a> WindowsPrefix<'a> {
+    /// Parse prefix from start of Windows path, if any
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path
+    #[must_use]
+    pub fn parse(path: &'a str) -> Option<Self> {
+        Self::split(path).map(|(prefix, _)| prefix)
+    }
+
+    /// Parse prefix from start of Windows path, if any, returning the
+    /// prefix and the remainder of the path following it
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path
+    #[must_use]
+    pub fn split(path: &'a str) -> Option<(Self, &'a str)> {
+        use self::WindowsPrefix::*;
+
+        if let Some(rest) = path.strip_prefix(r"\\?\") {
+            return Some(split_verbatim(rest));
+        }
+
+        match path.as_bytes() {
+            [a, b, b'.', c, ..] if is_separator(*a) && is_separator(*b) && is_separator(*c) => {
+                match split_segment(&path[4..], is_separator) {
+                    ("", _) => None,
+                    (name, rest) => Some((DeviceNs(name), rest)),
+                }
+            }
+            [a, b, ..] if is_separator(*a) && is_separator(*b) => {
+                let (server, share, rest) = split_server_share(&path[2..], is_separator);
+                match server {
+                    "" | "." | ".." => None,
+                    _ => Some((Unc(server, share), rest)),
+                }
+            }
+            [drive, b':', ..] if drive.is_ascii_alphabetic() => Some((Disk(*drive), &path[2..])),
+            _ => None,
+        }
+    }
+
+    /// Returns true if prefix is verbatim, i.e. the path must not be
+    /// normalized, false otherwise
+    #[must_use]
+    pub const fn is_verbatim(&self) -> bool {
+        matches!(
+            self,
+            Self::Verbatim(_) | Self::VerbatimUnc(..) | Self::VerbatimDisk(_)
+        )
+    }
+}
+
+const fn is_separator(b: u8) -> bool {
+    b == b'\\' || b == b'/'
+}
+
+const fn is_verbatim_separator(b: u8) -> bool {
+    b == b'\\'
+}
+
+fn split_verbatim(path: &str) -> (WindowsPrefix<'_>, &str) {
+    use self::WindowsPrefix::*;
+
+    if let Some(rest) = path.strip_prefix(r"UNC\") {
+        let (server, share, rest) = split_server_share(rest, is_verbatim_separator);
+        return (VerbatimUnc(server, share), rest);
+    }
+
+    match path.as_bytes() {
+        [drive, b':'] | [drive, b':', b'\\', ..] if drive.is_ascii_alphabetic() => {
+            (VerbatimDisk(*drive), &path[2..])
+        }
+        _ => {
+            let (name, rest) = split_segment(path, is_verbatim_separator);
+            (Verbatim(name), rest)
+        }
+    }
+}
+
+fn split_segment(path: &str, is_separator: fn(u8) -> bool) -> (&str, &str) {
+    let end = path.bytes().position(is_separator).unwrap_or(path.len());
+    path.split_at(end)
+}
+
+fn split_server_share(path: &str, is_separator: fn(u8) -> bool) -> (&str, &str, &str) {
+    let (server, rest) = split_segment(path, is_separator);
+    match rest.as_bytes() {
+        [b, ..] if is_separator(*b) => match split_segment(&rest[1..], is_separator) {
+            // Keep separator of empty share in prefix so that `\\server\\x`
+            // does not clean to `\\server\x` with `x` as the share
+            ("", after) if after.bytes().next().is_some_and(is_separator) => (server, "", after),
+            ("", _) => (server, "", rest),
+            (share, rest) => (server, share, rest),
+        },
+        _ => (server, "", rest),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WindowsPrefix::{self, *};
+
+    #[test]
+    fn test_no_prefix() {
+        assert_eq!(None, WindowsPrefix::split(""));
+        assert_eq!(None, WindowsPrefix::split("aaa\\bbb"));
+        assert_eq!(None, WindowsPrefix::split("\\aaa"));
+        assert_eq!(None, WindowsPrefix::split("\\\\"));
+        assert_eq!(None, WindowsPrefix::split("\\\\\\aaa"));
+        assert_eq!(None, WindowsPrefix::split("\\\\.."));
+        assert_eq!(None, WindowsPrefix::split("1:\\aaa"));
+    }
+
+    #[test]
+    fn test_disk() {
+        assert_eq!(Some((Disk(b'C'), "\\aaa")), WindowsPrefix::split("C:\\aaa"));
+        assert_eq!(Some((Disk(b'c'), "aaa")), WindowsPrefix::split("c:aaa"));
+        assert_eq!(Some((Disk(b'Z'), "")), WindowsPrefix::split("Z:"));
+    }
+
+    #[test]
+    fn test_unc() {
+        assert_eq!(
+            Some((Unc("srv", "share"), "\\aaa")),
+            WindowsPrefix::split("\\\\srv\\share\\aaa")
+        );
+        assert_eq!(
+            Some((Unc("srv", "share"), "/")),
+            WindowsPrefix::split("//srv/share/")
+        );
+        assert_eq!(Some((Unc("srv", ""), "")), WindowsPrefix::split("\\\\srv"));
+        assert_eq!(
+            Some((Unc("srv", ""), "\\aaa")),
+            WindowsPrefix::split("\\\\srv\\\\aaa")
+        );
+    }
+
+    #[test]
+    fn test_device_ns() {
+        assert_eq!(
+            Some((DeviceNs("PIPE"), "\\x")),
+            WindowsPrefix::split("\\\\.\\PIPE\\x")
+        );
+        assert_eq!(
+            Some((DeviceNs("C:"), "/aaa")),
+            WindowsPrefix::split("//./C:/aaa")
+        );
+        assert_eq!(None, WindowsPrefix::split("\\\\.\\"));
+        assert_eq!(None, WindowsPrefix::split("\\\\.\\\\x"));
+    }
+
+    #[test]
+    fn test_verbatim() {
+        assert_eq!(
+            Some((Verbatim("cat_pics"), "\\a")),
+            WindowsPrefix::split("\\\\?\\cat_pics\\a")
+        );
+        assert_eq!(
+            Some((VerbatimDisk(b'C'), "\\a\\..\\b")),
+            WindowsPrefix::split("\\\\?\\C:\\a\\..\\b")
+        );
+        assert_eq!(
+            Some((VerbatimDisk(b'C'), "")),
+            WindowsPrefix::split("\\\\?\\C:")
+        );
+        assert_eq!(
+            Some((Verbatim("C:a"), "")),
+            WindowsPrefix::split("\\\\?\\C:a")
+        );
+        assert_eq!(
+            Some((VerbatimUnc("srv", "share"), "\\a")),
+            WindowsPrefix::split("\\\\?\\UNC\\srv\\share\\a")
+        );
+        assert_eq!(
+            Some((Verbatim("C:/a/b"), "")),
+            WindowsPrefix::split("\\\\?\\C:/a/b")
+        );
+        assert!(!WindowsPrefix::parse("//?/C:/a").unwrap().is_verbatim());
+    }
+
+    #[test]
+    fn test_is_verbatim() {
+        assert!(Verbatim("a").is_verbatim());
+        assert!(VerbatimUnc("a", "b").is_verbatim());
+        assert!(VerbatimDisk(b'C').is_verbatim());
+        assert!(!DeviceNs("a").is_verbatim());
+        assert!(!Unc("a", "b").is_verbatim());
+        assert!(!Disk(b'C').is_verbatim());
+    }
+}
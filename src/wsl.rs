@@ -0,0 +1,273 @@
+// Copyright (c) 2020-3 Richard Cook
+//
+// Permission is hereby granted, free of charge, to any person obtaining
+// a copy of this software and associated documentation files (the
+// "Software"), to deal in the Software without restriction, including
+// without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to
+// permit persons to whom the Software is furnished to do so, subject to
+// the following conditions:
+//
+// The above copyright notice and this permission notice shall be
+// included in all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+// EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+// MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE
+// LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION
+// WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+//
+//! Conversion of paths between Windows and the Windows Subsystem for Linux
+//! (WSL), equivalent to the `wslpath` utility but performed lexically
+//!
+//! ```rust
+//! use joat_path::wsl::{to_mixed, to_unix, to_windows, WslConfig};
+//!
+//! let config = WslConfig::new("Ubuntu");
+//! assert_eq!(to_unix("C:\\Users\\me", &config).unwrap(), "/mnt/c/Users/me");
+//! assert_eq!(to_unix("\\\\wsl$\\Ubuntu\\home\\me", &config).unwrap(), "/home/me");
+//! assert_eq!(to_windows("/mnt/c/Users/me", &config).unwrap(), "C:\\Users\\me");
+//! assert_eq!(to_windows("/home/me", &config).unwrap(), "\\\\wsl.localhost\\Ubuntu\\home\\me");
+//! assert_eq!(to_mixed("/mnt/c/Users/me", &config).unwrap(), "C:/Users/me");
+//! ```
+use crate::error::{Error, Result};
+use crate::path_clean::{clean_unix, clean_windows};
+use crate::translate::{is_literal_on_unix, DriveMapping};
+use crate::windows_prefix::WindowsPrefix;
+use std::path::PathBuf;
+
+const UNC_HOST: &str = "wsl.localhost";
+const LEGACY_UNC_HOST: &str = "wsl$";
+
+/// Configuration of WSL distribution
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WslConfig {
+    distro: String,
+    mapping: DriveMapping,
+    legacy_unc_host: bool,
+}
+
+impl WslConfig {
+    /// Configuration of distribution with drives mounted under `/mnt`
+    ///
+    /// # Arguments
+    ///
+    /// * `distro` - Name of distribution, e.g. `Ubuntu`
+    #[must_use]
+    pub fn new(distro: &str) -> Self {
+        Self {
+            distro: String::from(distro),
+            mapping: DriveMapping::wsl(),
+            legacy_unc_host: false,
+        }
+    }
+
+    /// Mount drives under the given directory, corresponding to the
+    /// `automount.root` setting in `wsl.conf`
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - Unix directory containing drive directories
+    #[must_use]
+    pub fn mount_root(mut self, dir: &str) -> Self {
+        self.mapping = DriveMapping::with_prefix(dir);
+        self
+    }
+
+    /// Generate UNC paths using host `wsl$` used by older versions of
+    /// Windows instead of `wsl.localhost`
+    #[must_use]
+    pub const fn legacy_unc_host(mut self, value: bool) -> Self {
+        self.legacy_unc_host = value;
+        self
+    }
+
+    const fn unc_host(&self) -> &'static str {
+        if self.legacy_unc_host {
+            LEGACY_UNC_HOST
+        } else {
+            UNC_HOST
+        }
+    }
+}
+
+/// Convert Windows path to WSL path, equivalent to `wslpath -u`
+///
+/// Paths on drives are converted to paths under the mount root and UNC
+/// paths on `\\wsl$` or `\\wsl.localhost` are converted to paths within
+/// the distribution. Fails for UNC paths on other hosts or distributions
+/// and for verbatim paths that cannot be translated literally.
+///
+/// # Arguments
+///
+/// * `path` - Windows path
+/// * `config` - Configuration of distribution
+pub fn to_unix(path: &str, config: &WslConfig) -> Result<String> {
+    let path_clean = clean_windows(path);
+    match WindowsPrefix::split(&path_clean) {
+        Some((WindowsPrefix::VerbatimUnc(..), rest)) if !is_literal_on_unix(rest) => {
+            Err(Error::NotTranslatable(PathBuf::from(path)))
+        }
+        Some((
+            WindowsPrefix::Unc(server, share) | WindowsPrefix::VerbatimUnc(server, share),
+            rest,
+        )) if (server.eq_ignore_ascii_case(UNC_HOST)
+            || server.eq_ignore_ascii_case(LEGACY_UNC_HOST))
+            && share.eq_ignore_ascii_case(&config.distro) =>
+        {
+            Ok(clean_unix(&format!("/{}", rest.replace('\\', "/"))))
+        }
+        Some((WindowsPrefix::Unc(..) | WindowsPrefix::VerbatimUnc(..), _)) => {
+            Err(Error::NotTranslatable(PathBuf::from(path)))
+        }
+        _ => crate::translate::to_unix(&path_clean, &config.mapping)
+            .map_err(|_| Error::NotTranslatable(PathBuf::from(path))),
+    }
+}
+
+/// Convert WSL path to Windows path, equivalent to `wslpath -w`
+///
+/// Paths under the mount root are converted to paths on drives and other
+/// absolute paths are converted to UNC paths within the distribution.
+/// Relative paths are converted as by [`crate::to_windows`].
+///
+/// # Arguments
+///
+/// * `path` - WSL path
+/// * `config` - Configuration of distribution
+pub fn to_windows(path: &str, config: &WslConfig) -> Result<String> {
+    let path_clean = clean_unix(path);
+    match crate::translate::to_windows(&path_clean, &config.mapping) {
+        Ok(out) => Ok(out),
+        Err(_) if !path_clean.starts_with('/') || path_clean.contains('\\') => {
+            Err(Error::NotTranslatable(PathBuf::from(path)))
+        }
+        Err(_) => Ok(clean_windows(&format!(
+            "\\\\{}\\{}\\{}",
+            config.unc_host(),
+            config.distro,
+            path_clean.replace('/', "\\")
+        ))),
+    }
+}
+
+/// Convert WSL path to Windows path with forward slashes, equivalent to
+/// `wslpath -m`
+///
+/// # Arguments
+///
+/// * `path` - WSL path
+/// * `config` - Configuration of distribution
+pub fn to_mixed(path: &str, config: &WslConfig) -> Result<String> {
+    to_windows(path, config).map(|out| out.replace('\\', "/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{to_mixed, to_unix, to_windows, WslConfig};
+    use crate::error::Error;
+
+    #[test]
+    fn test_to_unix() {
+        let config = WslConfig::new("Ubuntu");
+        let tests = vec![
+            ("C:\\", "/mnt/c"),
+            ("C:\\Users\\me\\..\\you", "/mnt/c/Users/you"),
+            ("c:/Users/me/", "/mnt/c/Users/me"),
+            ("\\\\wsl$\\Ubuntu\\home\\me", "/home/me"),
+            ("\\\\wsl.localhost\\ubuntu\\home\\me", "/home/me"),
+            ("//WSL.LOCALHOST/Ubuntu/", "/"),
+            ("\\\\wsl$\\Ubuntu\\..\\etc", "/etc"),
+            ("aa\\bb", "aa/bb"),
+        ];
+
+        for test in tests {
+            assert_eq!(to_unix(test.0, &config).unwrap(), test.1);
+        }
+    }
+
+    #[test]
+    fn test_to_unix_fails() {
+        let config = WslConfig::new("Ubuntu");
+        for path in [
+            "\\\\wsl$\\Debian\\home",
+            "\\\\srv\\share\\aa",
+            "C:aa",
+            "\\aa",
+            "\\\\?\\UNC\\wsl$\\Ubuntu\\home\\..\\etc",
+        ] {
+            assert!(matches!(
+                to_unix(path, &config),
+                Err(Error::NotTranslatable(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_to_windows() {
+        let config = WslConfig::new("Ubuntu");
+        let tests = vec![
+            ("/mnt/c", "C:\\"),
+            ("/mnt/c/Users/./me/", "C:\\Users\\me"),
+            ("/home/me", "\\\\wsl.localhost\\Ubuntu\\home\\me"),
+            ("/", "\\\\wsl.localhost\\Ubuntu\\"),
+            ("/mnt", "\\\\wsl.localhost\\Ubuntu\\mnt"),
+            ("/mnt/wsl", "\\\\wsl.localhost\\Ubuntu\\mnt\\wsl"),
+            ("aa/bb", "aa\\bb"),
+            ("x/../c:foo", ".\\c:foo"),
+        ];
+
+        for test in tests {
+            assert_eq!(to_windows(test.0, &config).unwrap(), test.1);
+        }
+
+        let config = WslConfig::new("Ubuntu").legacy_unc_host(true);
+        assert_eq!(
+            to_windows("/home/me", &config).unwrap(),
+            "\\\\wsl$\\Ubuntu\\home\\me"
+        );
+    }
+
+    #[test]
+    fn test_to_windows_fails() {
+        let config = WslConfig::new("Ubuntu");
+        for path in ["a\\b", "/home/a\\b", "/mnt/c/a\\b"] {
+            assert!(matches!(
+                to_windows(path, &config),
+                Err(Error::NotTranslatable(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_to_mixed() {
+        let config = WslConfig::new("Ubuntu");
+        assert_eq!(to_mixed("/mnt/c/Users/me", &config).unwrap(), "C:/Users/me");
+        assert_eq!(
+            to_mixed("/home/me", &config).unwrap(),
+            "//wsl.localhost/Ubuntu/home/me"
+        );
+    }
+
+    #[test]
+    fn test_mount_root() {
+        let config = WslConfig::new("Ubuntu").mount_root("/");
+        assert_eq!(to_unix("C:\\aa", &config).unwrap(), "/c/aa");
+        assert_eq!(to_windows("/c/aa", &config).unwrap(), "C:\\aa");
+        assert_eq!(
+            to_windows("/mnt/c/aa", &config).unwrap(),
+            "\\\\wsl.localhost\\Ubuntu\\mnt\\c\\aa"
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let config = WslConfig::new("Ubuntu");
+        for path in ["/mnt/d/aa/bb", "/home/me", "/", "aa/bb"] {
+            let windows = to_windows(path, &config).unwrap();
+            assert_eq!(to_unix(&windows, &config).unwrap(), path);
+        }
+    }
+}